@@ -1,6 +1,8 @@
 // #![feature(binary_heap_into_iter_sorted, iter_next_chunk)]
 use std::{collections::{BinaryHeap, HashMap}, iter};
 
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
 #[aoc(day1, part1)]
 pub fn part1(input: &str) -> u64 {
 	let (mut left, mut right) = (BinaryHeap::new(), BinaryHeap::new());
@@ -19,6 +21,26 @@ pub fn part1(input: &str) -> u64 {
 	iter::zip(left.into_iter_sorted(), right.into_iter_sorted()).map(|(l, r)| l.abs_diff(r)).sum()
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(1, input);
+	for line in input.lines() {
+		let mut values = line.split_whitespace();
+		for _ in 0..2 {
+			let value = values.next().ok_or_else(|| checker.error_at_end(line, ParseErrorKind::UnexpectedEnd))?;
+			checker.number::<u64>(value)?;
+		}
+		if let Some(extra) = values.next() {
+			return Err(checker.error_at(extra, ParseErrorKind::UnexpectedByte(extra.as_bytes()[0])));
+		}
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 #[aoc(day1, part2)]
 pub fn part2(input: &str) -> u64 {
 	let (mut left, mut right) = (HashMap::new(), HashMap::new());
@@ -37,6 +59,10 @@ pub fn part2(input: &str) -> u64 {
 	map(left, |(value, occurances)| value * occurances * right.get(&value).copied().unwrap_or(0)).sum()
 }
 
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn map<I: IntoIterator, O, F: FnMut(I::Item) -> O>(iterable: I, f: F) -> iter::Map<I::IntoIter, F> {
 	iterable.into_iter().map(f)
 }
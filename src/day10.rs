@@ -1,178 +1,7 @@
-use std::{iter, marker::PhantomData, num::NonZero, ptr::NonNull, slice};
+use std::{iter, mem, num::NonZero};
 
-struct GridRefWithLn<'a, T> {
-	grid_ptr: NonNull<T>,
-	total_length_with_ln: NonZero<usize>,
-	row_length_with_ln: NonZero<usize>,
-	marker: PhantomData<&'a [T]>
-}
-
-impl<'a, T> Copy for GridRefWithLn<'a, T> {}
-
-impl<'a, T> Clone for GridRefWithLn<'a, T> {
-	fn clone(&self) -> Self {
-		*self
-	}
-}
-
-impl<'a, T> GridRefWithLn<'a, T> {
-	pub fn from_slice(grid: &'a [T], row_length: NonZero<usize>) -> Option<Self> {
-		let row_length_with_ln = row_length.checked_add(1)?;
-		let total_length_with_ln = NonZero::new(
-			if grid.len() % row_length_with_ln == 0 {
-				grid.len()
-			} else {
-				grid.len() + 1
-			}
-		)?;
-		
-		(total_length_with_ln.get() % row_length_with_ln == 0).then(||
-			Self {
-				grid_ptr: unsafe { NonNull::new_unchecked(grid.as_ptr() as *mut _) },
-				total_length_with_ln,
-				row_length_with_ln,
-				marker: PhantomData
-			}
-		)
-	}
-	
-	pub unsafe fn from_raw_parts(grid_ptr: NonNull<T>, total_length_with_ln: NonZero<usize>, row_length_with_ln: NonZero<usize>) -> Self {
-		Self { grid_ptr, total_length_with_ln, row_length_with_ln, marker: PhantomData }
-	}
-	
-	
-	pub fn as_slice(&self) -> &'a [T] { // Does not include the final possibly-uninitialized '\n'
-		unsafe { slice::from_raw_parts(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
-	}
-	
-	pub fn as_ptr(&self) -> NonNull<T> {
-		self.grid_ptr
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get(&self, row: usize, col: usize) -> Option<&'a T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
-	}
-	
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &'a T {
-		unsafe { self.as_slice().get_unchecked(self.to_index_unchecked(row, col)) }
-	}
-	
-	
-	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
-		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
-	}
-	
-	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
-		row * self.row_length_with_ln().get() + col
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get_row(&self, row: usize) -> Option<&[T]> {
-		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_slice().get(row_start..row_end)
-	}
-	
-	fn rows(&self) -> Rows<T> {
-		Rows::over(*self)
-	}
-	
-	
-	pub fn row_count(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.total_length_with_ln().get() / self.row_length_with_ln()) }
-	}
-	
-	pub fn row_length(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.row_length_with_ln().get() - 1) }
-	}
-	
-	pub fn row_length_with_ln(&self) -> NonZero<usize> {
-		self.row_length_with_ln
-	}
-	
-	pub fn total_length_with_ln(&self) -> NonZero<usize> {
-		self.total_length_with_ln // unsafe { NonZero::new_unchecked(self.slice().len()) }
-	}
-}
-
-struct Rows<'a, T> {
-	src: Option<GridRefWithLn<'a, T>>,
-}
-
-impl<'a, T> Clone for Rows<'a, T> {
-	fn clone(&self) -> Self {
-		Self { src: self.as_grid_ref() }
-	}
-}
-
-impl<'a, T> Rows<'a, T> {
-	fn over(src: GridRefWithLn<'a, T>) -> Self {
-		Self { src: src.into() }
-	}
-	
-	fn as_grid_ref(&self) -> Option<GridRefWithLn<'a, T>> {
-		self.src
-	}
-	
-	#[allow(unused)]
-	fn empty() -> Self {
-		Self { src: None }
-	}
-}
-
-impl<'a, T> Iterator for Rows<'a, T> {
-	type Item = &'a [T];
-	
-	fn next(&mut self) -> Option<Self::Item> {
-		self.src.map(|src| {
-			let slice = unsafe { src.as_slice().get_unchecked(..src.row_length().get()) };
-			
-			let (ptr, total_ln, row_ln) = (src.as_ptr(), src.total_length_with_ln(), src.row_length_with_ln());
-			
-			self.src = NonZero::new(unsafe { total_ln.get().unchecked_sub(row_ln.get()) }).map(|new_total_ln| unsafe {
-				let new_ptr = ptr.add(row_ln.get());
-				GridRefWithLn::from_raw_parts(new_ptr, new_total_ln, row_ln)
-			});
-			
-			slice
-		})
-	}
-	
-	fn size_hint(&self) -> (usize, Option<usize>) {
-		let len = self.len();
-		(len, len.into())
-	}
-}
-
-impl<'a, T> DoubleEndedIterator for Rows<'a, T> {
-	fn next_back(&mut self) -> Option<Self::Item> {
-		self.src.map(|src| {
-			let (ptr, total_ln, row_ln) = (src.as_ptr(), src.total_length_with_ln(), src.row_length_with_ln());
-			
-			let start = unsafe { total_ln.get().unchecked_sub(row_ln.get()) };
-			let end = start + src.row_length().get();
-			
-			let slice = unsafe { src.as_slice().get_unchecked(start..end) };
-			
-			self.src = NonZero::new(start).map(|new_total_ln| unsafe {
-				GridRefWithLn::from_raw_parts(ptr, new_total_ln, row_ln)
-			});
-			
-			slice
-		})
-	}
-}
-
-impl<'a, T> ExactSizeIterator for Rows<'a, T> {
-	fn len(&self) -> usize {
-		self.src.map(|src| src.total_length_with_ln().get() / src.row_length_with_ln()).unwrap_or(0)
-	}
-}
+use crate::error::{Checker, ParseError};
+use crate::grid::GridRefWithLn;
 
 // --------------v-
 //>99.....989...989  0 -8
@@ -257,8 +86,116 @@ fn trailhead_score(grid: &GridRefWithLn<u8>, row: usize, col: usize) -> u32 {
 	reachable_nines(grid, (row, col), b'0', &mut [0u16; 16], (0, 0))
 }
 
+// Trails go from 'start' to 'end', changing height by exactly 'step' each time. Heights are the digits on the map.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct TrailRules {
+	pub start: u8,
+	pub end: u8,
+	pub step: i8
+}
+
+impl TrailRules {
+	pub const DEFAULT: Self = Self { start: 0, end: 9, step: 1 };
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Trailhead {
+	pub position: (usize, usize),
+	// How many summits can be reached.
+	pub score: usize,
+	// How many different trails there are.
+	pub rating: u64,
+	// In reading order.
+	pub summits: Vec<(usize, usize)>
+}
+
+#[derive(Copy, Clone)]
+pub struct TrailMap<'a> {
+	grid: GridRefWithLn<'a, u8>
+}
+
+impl<'a> TrailMap<'a> {
+	pub fn parse(input: &'a str) -> Option<Self> {
+		GridRefWithLn::from_text(input).map(|grid| Self { grid })
+	}
+	
+	pub fn try_parse(input: &'a str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+	
+	// Anything that isn't a digit can't be walked on.
+	fn height(&self, index: usize) -> Option<u8> {
+		Some(self.grid.as_slice()[index].wrapping_sub(b'0')).filter(|&height| height <= 9)
+	}
+	
+	// Every position at 'rules.start', whether or not any trails lead from it.
+	pub fn trailheads(&self, rules: TrailRules) -> Vec<Trailhead> {
+		assert!(rules.step != 0, "trails must go somewhere");
+		
+		let mut by_height = vec![Vec::new(); 10];
+		for index in 0..self.grid.as_slice().len() {
+			if let Some(height) = self.height(index) {
+				by_height[height as usize].push(index);
+			}
+		}
+		
+		// Each level of the trail, working backwards from the summits. Trails can only exist if 'end' is a whole number of steps from 'start'.
+		let (distance, step) = (rules.end as i32 - rules.start as i32, rules.step as i32);
+		let in_range = rules.start <= 9 && rules.end <= 9;
+		let levels: Vec<i32> = if in_range && distance % step == 0 && distance / step >= 0 {
+			map(0..=distance / step, |k| rules.end as i32 - k * step).collect()
+		} else {
+			Vec::new()
+		};
+		
+		let mut ratings = vec![0u64; self.grid.as_slice().len()];
+		let mut summits: Vec<Vec<usize>> = vec![Vec::new(); self.grid.as_slice().len()];
+		if !levels.is_empty() {
+			for &index in &by_height[rules.end as usize] {
+				ratings[index] = 1;
+				summits[index].push(index);
+			}
+			
+			for pair in levels.windows(2) {
+				let (higher, height) = (pair[0] as usize, pair[1] as usize);
+				for &index in &by_height[height] {
+					let (row, col) = self.grid.to_row_col_unchecked(index);
+					let mut reachable = Vec::new();
+					for next in map(self.grid.neighbors(row, col), |(r, c)| self.grid.to_index_unchecked(r, c)) {
+						if self.height(next) == Some(higher as u8) {
+							ratings[index] += ratings[next];
+							reachable.extend_from_slice(&summits[next]);
+						}
+					}
+					
+					reachable.sort_unstable();
+					reachable.dedup();
+					summits[index] = reachable;
+				}
+				
+				// Nothing looks at the level above this one again.
+				for &index in &by_height[higher] {
+					mem::take(&mut summits[index]);
+				}
+			}
+		}
+		
+		map(by_height.get(rules.start as usize).into_iter().flatten(), |&index| {
+			let summits: Vec<_> = map(&summits[index], |&summit| self.grid.to_row_col_unchecked(summit)).collect();
+			Trailhead { position: self.grid.to_row_col_unchecked(index), score: summits.len(), rating: ratings[index], summits }
+		}).collect()
+	}
+}
+
 #[aoc(day10, part1)]
 pub fn part1(input: &str) -> u32 {
+	let trails = unsafe { TrailMap::parse(input).unwrap_unchecked() };
+	trails.trailheads(TrailRules::DEFAULT).iter().map(|trailhead| trailhead.score as u32).sum()
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> u32 {
 	let line_length = input.find('\n').unwrap_or(input.len());
 	let Some(grid) = NonZero::new(line_length).and_then(|line_length| GridRefWithLn::from_slice(input.as_bytes(), line_length)) else {
 		unreachable!();
@@ -270,10 +207,18 @@ pub fn part1(input: &str) -> u32 {
 			total += trailhead_score(&grid, row, col);
 		}
 	}
-			
+	
 	total
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	Checker::new(10, input).grid(input, |b| b.is_ascii_digit() || b == b'.').map(|_| ())
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 fn paths_to_nine(grid: &GridRefWithLn<u8>, at: (usize, usize), at_char: u8) -> u32 {
 	if at_char >= b'9' {
 		1
@@ -298,7 +243,13 @@ fn trailhead_rating(grid: &GridRefWithLn<u8>, row: usize, col: usize) -> u32 {
 }
 
 #[aoc(day10, part2)]
-pub fn part2(input: &str) -> u32 {
+pub fn part2(input: &str) -> u64 {
+	let trails = unsafe { TrailMap::parse(input).unwrap_unchecked() };
+	trails.trailheads(TrailRules::DEFAULT).iter().map(|trailhead| trailhead.rating).sum()
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u32 {
 	let line_length = input.find('\n').unwrap_or(input.len());
 	let Some(grid) = NonZero::new(line_length).and_then(|line_length| GridRefWithLn::from_slice(input.as_bytes(), line_length)) else {
 		unreachable!();
@@ -310,10 +261,18 @@ pub fn part2(input: &str) -> u32 {
 			total += trailhead_rating(&grid, row, col);
 		}
 	}
-			
+	
 	total
 }
 
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
+
+fn map<I: IntoIterator, O, F: FnMut(I::Item) -> O>(i: I, f: F) -> iter::Map<I::IntoIter, F> {
+	i.into_iter().map(f)
+}
@@ -1,68 +1,207 @@
-use std::mem;
+use std::{fmt, mem};
 
 use rustc_hash::{FxBuildHasher, FxHashMap as HashMap};
 
-#[aoc(day11, part1)]
-pub fn part1(input: &str) -> u32 {
-	let mut current = HashMap::with_capacity_and_hasher(800, FxBuildHasher);
-	
-	for num in input.split_whitespace() {
-		let num: u64 = unsafe { num.parse().unwrap_unchecked() };
-		*current.entry(num).or_insert(0u32) += 1;
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
+// How many stones share an engraving. Implement this for a big-integer type to go beyond 'u128'.
+pub trait Count: Clone {
+	fn zero() -> Self;
+	fn one() -> Self;
+	// 'None' if the sum doesn't fit.
+	fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_count {
+	($($t:ty),*) => {$(
+		impl Count for $t {
+			fn zero() -> Self { 0 }
+			fn one() -> Self { 1 }
+			fn checked_add(&self, other: &Self) -> Option<Self> { <$t>::checked_add(*self, *other) }
+		}
+	)*};
+}
+
+impl_count!(u32, u64, u128);
+
+// What a single stone turns into when you blink. Returns 'None' if a new engraving wouldn't fit in a 'u64'.
+pub trait Rules {
+	fn blink(&self, stone: u64, emit: impl FnMut(u64)) -> Option<()>;
+}
+
+// 0 becomes 1, an even number of digits splits down the middle, and anything else is multiplied by 2024.
+#[derive(Copy, Clone, Default)]
+pub struct DefaultRules;
+
+impl Rules for DefaultRules {
+	fn blink(&self, num: u64, mut emit: impl FnMut(u64)) -> Option<()> {
+		if let Some(digits_minus_one) = num.checked_ilog10() {
+			if digits_minus_one % 2 == 0 { // Odd digit count.
+				emit(num.checked_mul(2024)?);
+			} else { // Even digit count.
+				let pow = 10u64.pow(digits_minus_one.div_ceil(2));
+				emit(num / pow);
+				emit(num % pow);
+			}
+		} else {
+			emit(1);
+		}
+		
+		Some(())
+	}
+}
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum Overflow {
+	Engraving(u64), // Blinking at this stone would engrave a number too big for a 'u64'.
+	Count // There are more stones than the count type can hold.
+}
+
+impl fmt::Display for Overflow {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Engraving(num) => write!(f, "stone {num} would become too big to engrave"),
+			Self::Count => write!(f, "too many stones to count")
+		}
+	}
+}
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Step<C> {
+	pub blinks: usize,
+	pub distinct: usize,
+	pub total: C
+}
+
+// The line of stones as a multiset, since their order never matters.
+#[derive(Clone)]
+pub struct Stones<C: Count = u64, R: Rules = DefaultRules> {
+	current: HashMap<u64, C>,
+	target: HashMap<u64, C>,
+	rules: R,
+	blinks: usize
+}
+
+impl<C: Count> Stones<C> {
+	pub fn parse(input: &str) -> Option<Self> {
+		let mut stones = Vec::with_capacity(8);
+		for num in input.split_whitespace() {
+			stones.push(num.parse().ok()?);
+		}
+		
+		Some(Self::with_rules(stones, DefaultRules))
 	}
 	
-	let mut target = HashMap::with_capacity_and_hasher(800, FxBuildHasher);
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+}
+
+impl<C: Count, R: Rules> Stones<C, R> {
+	pub fn with_rules(stones: impl IntoIterator<Item = u64>, rules: R) -> Self {
+		let mut current = HashMap::with_capacity_and_hasher(4000, FxBuildHasher);
+		for num in stones {
+			let times = current.entry(num).or_insert_with(C::zero);
+			*times = times.checked_add(&C::one()).expect("too many stones to count");
+		}
+		
+		Self { current, target: HashMap::with_capacity_and_hasher(4000, FxBuildHasher), rules, blinks: 0 }
+	}
 	
-	for _ in 0..25 {
-		// target.clear();
-		for (num, times) in current.drain() {
-			if let Some(digits_minus_one) = num.checked_ilog10() {
-				if digits_minus_one % 2 == 0 { // Odd digit count.
-					*target.entry(num * 2024).or_insert(0) += times;
-				} else { // Even digit count.
-					let pow = 10u64.pow((digits_minus_one + 1) / 2);
-					for half in [num / pow, num % pow] {
-						*target.entry(half).or_insert(0) += times;
-					}
+	// Leaves the stones as they were if anything overflows.
+	pub fn blink(&mut self) -> Result<(), Overflow> {
+		let mut overflowed = false;
+		for (&num, times) in &self.current {
+			let emitted = self.rules.blink(num, |new| {
+				let count = self.target.entry(new).or_insert_with(C::zero);
+				match count.checked_add(times) {
+					Some(sum) => *count = sum,
+					None => overflowed = true
 				}
-			} else {
-				*target.entry(1).or_insert(0) += times;
+			});
+			
+			if emitted.is_none() || overflowed {
+				self.target.clear();
+				return Err(if overflowed { Overflow::Count } else { Overflow::Engraving(num) });
 			}
 		}
-		mem::swap(&mut target, &mut current);
+		
+		self.current.clear();
+		mem::swap(&mut self.target, &mut self.current);
+		self.blinks += 1;
+		Ok(())
 	}
 	
-	current.into_values().sum()
-}
-
-#[aoc(day11, part2)]
-pub fn part2(input: &str) -> u64 {
-	let mut current = HashMap::with_capacity_and_hasher(4000, FxBuildHasher);
+	pub fn blink_times(&mut self, times: usize) -> Result<&mut Self, Overflow> {
+		for _ in 0..times {
+			self.blink()?;
+		}
+		Ok(self)
+	}
 	
-	for num in input.split_whitespace() {
-		let num: u64 = unsafe { num.parse().unwrap_unchecked() };
-		*current.entry(num).or_insert(0u64) += 1;
+	// Blinks 'times' more times, recording the state after each one.
+	pub fn growth(&mut self, times: usize) -> Result<Vec<Step<C>>, Overflow> {
+		let mut steps = Vec::with_capacity(times);
+		for _ in 0..times {
+			self.blink()?;
+			steps.push(self.step()?);
+		}
+		Ok(steps)
 	}
 	
-	let mut target = HashMap::with_capacity_and_hasher(4000, FxBuildHasher);
+	pub fn step(&self) -> Result<Step<C>, Overflow> {
+		Ok(Step { blinks: self.blinks, distinct: self.distinct(), total: self.total()? })
+	}
 	
-	for _ in 0..75 {
-		for (num, times) in current.drain() {
-			if let Some(digits_minus_one) = num.checked_ilog10() {
-				if digits_minus_one % 2 == 0 { // Odd digit count.
-					*target.entry(num * 2024).or_insert(0) += times;
-				} else { // Even digit count.
-					let pow = 10u64.pow((digits_minus_one + 1) / 2);
-					for half in [num / pow, num % pow] {
-						*target.entry(half).or_insert(0) += times;
-					}
-				}
-			} else {
-				*target.entry(1).or_insert(0) += times;
-			}
-		}
-		mem::swap(&mut target, &mut current);
+	pub fn blinks(&self) -> usize {
+		self.blinks
 	}
 	
-	current.into_values().sum()
+	// How many different numbers are engraved across all the stones.
+	pub fn distinct(&self) -> usize {
+		self.current.len()
+	}
+	
+	pub fn total(&self) -> Result<C, Overflow> {
+		self.current.values().try_fold(C::zero(), |acc, times| acc.checked_add(times).ok_or(Overflow::Count))
+	}
+	
+	pub fn counts(&self) -> impl Iterator<Item = (u64, &C)> {
+		self.current.iter().map(|(&num, times)| (num, times))
+	}
+}
+
+#[aoc(day11, part1)]
+pub fn part1(input: &str) -> u32 {
+	let mut stones = unsafe { Stones::parse(input).unwrap_unchecked() };
+	stones.blink_times(25).and_then(|stones| stones.total()).unwrap()
+}
+
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(11, input);
+	for num in input.split_whitespace() {
+		checker.number::<u64>(num)?;
+	}
+	
+	Ok(())
+}
+
+fn try_count_after<C: Count>(input: &str, blinks: usize) -> Result<C, ParseError> {
+	let mut stones = Stones::try_parse(input)?;
+	stones.blink_times(blinks).and_then(|stones| stones.total()).map_err(|_| Checker::new(11, input).error_at_end(input, ParseErrorKind::OutOfRange))
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	try_count_after(input, 25)
+}
+
+#[aoc(day11, part2)]
+pub fn part2(input: &str) -> u64 {
+	let mut stones = unsafe { Stones::parse(input).unwrap_unchecked() };
+	stones.blink_times(75).and_then(|stones| stones.total()).unwrap()
+}
+
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	try_count_after(input, 75)
 }
@@ -1,127 +1,10 @@
 use rustc_hash::{FxHashMap as HashMap};
 
 use std::collections::{/* HashMap, */ BTreeSet};
-use std::{iter, marker::PhantomData, mem, num::NonZero, ops, ptr::NonNull, slice};
+use std::{iter, mem, num::NonZero, ops};
 
-struct GridRefMutWithLn<'a, T> {
-	grid_ptr: NonNull<T>,
-	total_length_with_ln: NonZero<usize>,
-	row_length_with_ln: NonZero<usize>,
-	marker: PhantomData<&'a mut [T]>
-}
-
-impl<'a, T> GridRefMutWithLn<'a, T> {
-	pub fn from_slice(grid: &'a mut [T], row_length: NonZero<usize>) -> Option<Self> {
-		let row_length_with_ln = row_length.checked_add(1)?;
-		let total_length_with_ln = NonZero::new(
-			if grid.len() % row_length_with_ln == 0 {
-				grid.len()
-			} else {
-				grid.len() + 1
-			}
-		)?;
-		
-		(total_length_with_ln.get() % row_length_with_ln == 0).then(||
-			Self {
-				grid_ptr: unsafe { NonNull::new_unchecked(grid.as_ptr() as *mut _) },
-				total_length_with_ln,
-				row_length_with_ln,
-				marker: PhantomData
-			}
-		)
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn from_raw_parts(grid_ptr: NonNull<T>, total_length_with_ln: NonZero<usize>, row_length_with_ln: NonZero<usize>) -> Self {
-		Self { grid_ptr, total_length_with_ln, row_length_with_ln, marker: PhantomData }
-	}
-	
-	
-	pub fn as_slice(&self) -> &'a [T] { // Does not include the final possibly-uninitialized '\n'
-		unsafe { slice::from_raw_parts(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
-	}
-	
-	pub fn as_mut_slice(&mut self) -> &'a mut [T] { // Does not include the final possibly-uninitialized '\n'
-		unsafe { slice::from_raw_parts_mut(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
-	}
-	
-	#[allow(unused)]
-	pub fn as_mut_ptr(&mut self) -> NonNull<T> {
-		self.grid_ptr
-	}
-	
-	#[allow(unused)]
-	pub fn as_ptr(&self) -> NonNull<T> {
-		self.grid_ptr
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get(&self, row: usize, col: usize) -> Option<&'a T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
-	}
-	
-	pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&'a mut T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_mut_slice().get_unchecked_mut(i) })
-	}
-	
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &'a T {
-		unsafe { self.as_slice().get_unchecked(self.to_index_unchecked(row, col)) }
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked_mut(&mut self, row: usize, col: usize) -> &'a mut T {
-		let index = self.to_index_unchecked(row, col);
-		unsafe { self.as_mut_slice().get_unchecked_mut(index) }
-	}
-	
-	
-	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
-		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
-	}
-	
-	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
-		row * self.row_length_with_ln().get() + col
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get_row(&self, row: usize) -> Option<&'a [T]> {
-		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_slice().get(row_start..row_end)
-	}
-	
-	#[allow(unused)]
-	pub fn get_row_mut(&mut self, row: usize) -> Option<&'a mut [T]> {
-		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_mut_slice().get_mut(row_start..row_end)
-	}
-	
-//	fn rows(&self) -> Rows<T> {
-//		Rows::over(*self)
-//	}
-	
-	
-	pub fn row_count(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.total_length_with_ln().get() / self.row_length_with_ln()) }
-	}
-	
-	pub fn row_length(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.row_length_with_ln().get() - 1) }
-	}
-	
-	pub fn row_length_with_ln(&self) -> NonZero<usize> {
-		self.row_length_with_ln
-	}
-	
-	pub fn total_length_with_ln(&self) -> NonZero<usize> {
-		self.total_length_with_ln
-	}
-}
+use crate::error::{Checker, ParseError};
+use crate::grid::GridRefMutWithLn;
 
 const FLAG: u8 = 0b1000_0000u8;
 
@@ -197,6 +80,15 @@ pub fn part1(input: &str) -> u64 {
 	total
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	// The flood fills mark visited plots using the high bit, so anything outside of ASCII would be confused for an already-visited plot.
+	Checker::new(12, input).grid(input, |b| b.is_ascii()).map(|_| ())
+}
+
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum Direction {
 	Right = 0, Down, Left, Up
@@ -338,6 +230,10 @@ pub fn part2(input: &str) -> u64 {
 	total
 }
 
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
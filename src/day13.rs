@@ -1,13 +1,15 @@
 // #![feature(iter_next_chunk)]
-use std::num;
+use std::{num, str};
 
-#[derive(Copy, Eq, Clone, PartialEq)]
-struct Position {
-	pub x: u64,
-	pub y: u64
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Position<T = u64> {
+	pub x: T,
+	pub y: T
 }
 
-fn try_extract_pos(line: &str) -> Result<Position, Option<num::ParseIntError>> {
+fn try_extract_pos<T: str::FromStr<Err = num::ParseIntError>>(line: &str) -> Result<Position<T>, Option<num::ParseIntError>> {
 	let (x, y) = line.split_once(',').ok_or(None)?;
 	let (x, y) = (x.trim_matches(|c: char| !c.is_ascii_digit()), y.trim_matches(|c: char| !c.is_ascii_digit()));
 	
@@ -55,8 +57,150 @@ fn tokens_to_win(button_a: Position, button_b: Position, prize: Position) -> Opt
 	Some(a_times * 3 + b_times)
 }
 
+// Token prices for each button. Buttons are limited to 32 bits (as are prices) and prizes to 64, which keeps everything below in range of an 'i128'.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Prices {
+	pub a: u32,
+	pub b: u32
+}
+
+impl Prices {
+	pub const DEFAULT: Self = Self { a: 3, b: 1 };
+}
+
+const PRIZE_OFFSET: u64 = 10000000000000;
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Presses {
+	pub a: u128,
+	pub b: u128
+}
+
+impl Presses {
+	pub fn cost(self, prices: Prices) -> u128 {
+		self.a * prices.a as u128 + self.b * prices.b as u128
+	}
+}
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct ClawMachine {
+	pub button_a: Position<u32>,
+	pub button_b: Position<u32>,
+	pub prize: Position<u64>
+}
+
+// Returns '(g, s)' with 'g = gcd(a, b)' and 's * a ≡ g (mod b)'.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128) {
+	let (mut old_r, mut r) = (a, b);
+	let (mut old_s, mut s) = (1, 0);
+	while r != 0 {
+		let q = old_r / r;
+		(old_r, r) = (r, old_r - q * r);
+		(old_s, s) = (s, old_s - q * s);
+	}
+	
+	(old_r, old_s)
+}
+
+// Cheapest non-negative 'a', 'b' with 'u·a + v·b = w', where 'u', 'v' and 'w' are all non-negative.
+fn solve_line(u: i128, v: i128, w: i128, prices: Prices) -> Option<(i128, i128)> {
+	match (u, v) {
+		(0, 0) => (w == 0).then_some((0, 0)),
+		(0, _) => (w % v == 0).then(|| (0, w / v)),
+		(_, 0) => (w % u == 0).then(|| (w / u, 0)),
+		_ => {
+			let (g, s) = extended_gcd(u, v);
+			if w % g != 0 {
+				return None;
+			}
+			
+			// Every solution is '(a + k·step_a, b - k·step_b)', so the cost changes linearly with 'k' and is cheapest at one end.
+			let (step_a, step_b) = (v / g, u / g);
+			let (a, b) = if step_a * prices.a as i128 >= step_b * prices.b as i128 {
+				let a = ((w / g) % step_a * s).rem_euclid(step_a);
+				(a, (w - u * a) / v)
+			} else {
+				let (_, t) = extended_gcd(v, u);
+				let b = ((w / g) % step_b * t).rem_euclid(step_b);
+				((w - v * b) / u, b)
+			};
+			
+			(a >= 0 && b >= 0).then_some((a, b))
+		}
+	}
+}
+
+impl ClawMachine {
+	fn try_from_lines(button_a: &str, button_b: &str, prize: &str) -> Option<Self> {
+		Some(Self { button_a: try_extract_pos(button_a).ok()?, button_b: try_extract_pos(button_b).ok()?, prize: try_extract_pos(prize).ok()? })
+	}
+	
+	pub fn parse_all(input: &str) -> Option<Vec<Self>> {
+		let mut lines = input.lines();
+		let mut machines = Vec::with_capacity(320);
+		while let Some(button_a) = lines.next() {
+			machines.push(Self::try_from_lines(button_a, lines.next()?, lines.next()?)?);
+			lines.next();
+		}
+		
+		Some(machines)
+	}
+	
+	pub fn try_parse_all(input: &str) -> Result<Vec<Self>, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse_all(input).unwrap_unchecked() })
+	}
+	
+	// The cheapest way to reach the prize after moving it 'offset' along both axes, if there is one.
+	// When the buttons push the claw in the same direction, every solution is considered rather than just the first.
+	pub fn presses(self, prices: Prices, offset: u64) -> Option<Presses> {
+		let [ax, ay, bx, by] = [self.button_a.x, self.button_a.y, self.button_b.x, self.button_b.y].map(i128::from);
+		let (px, py) = (self.prize.x as i128 + offset as i128, self.prize.y as i128 + offset as i128);
+		
+		let det = ax * by - ay * bx;
+		let (a, b) = if det != 0 {
+			let (a_num, b_num) = (px * by - py * bx, ax * py - ay * px);
+			if a_num % det != 0 || b_num % det != 0 || a_num / det < 0 || b_num / det < 0 {
+				return None;
+			}
+			(a_num / det, b_num / det)
+		} else {
+			// Both buttons lie on one line through the origin, so the prize has to as well.
+			let (dx, dy) = if (ax, ay) != (0, 0) { (ax, ay) } else { (bx, by) };
+			if dx * py != dy * px {
+				return None;
+			}
+			
+			// Along that line, one coordinate is enough to tell points apart.
+			if (dx, dy) == (0, 0) {
+				solve_line(0, 0, px | py, prices)?
+			} else if dx != 0 {
+				solve_line(ax, bx, px, prices)?
+			} else {
+				solve_line(ay, by, py, prices)?
+			}
+		};
+		
+		Some(Presses { a: a as u128, b: b as u128 })
+	}
+	
+	pub fn cost(self, prices: Prices, offset: u64) -> Option<u128> {
+		self.presses(prices, offset).map(|presses| presses.cost(prices))
+	}
+}
+
+pub fn total_cost(machines: &[ClawMachine], prices: Prices, offset: u64) -> u128 {
+	machines.iter().filter_map(|machine| machine.cost(prices, offset)).sum()
+}
+
 #[aoc(day13, part1)]
-pub fn part1(input: &str) -> u64 {
+pub fn part1(input: &str) -> u128 {
+	let machines = unsafe { ClawMachine::parse_all(input).unwrap_unchecked() };
+	total_cost(&machines, Prices::DEFAULT, 0)
+}
+
+#[allow(unused)]
+pub fn part1_u64(input: &str) -> u64 {
 	let mut lines = input.lines();
 	
 	let mut tokens = 0;
@@ -79,18 +223,53 @@ pub fn part1(input: &str) -> u64 {
 	tokens
 }
 
+fn check_position<T: str::FromStr>(checker: Checker, line: &str, x_prefix: &str, y_prefix: &str) -> Result<(), ParseError> {
+	let (x, y) = checker.split_once(checker.prefix(line, x_prefix)?, ", Y")?;
+	checker.number::<T>(x)?;
+	checker.number::<T>(checker.prefix(y, y_prefix)?).map(|_| ())
+}
+
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(13, input);
+	let mut lines = input.lines();
+	
+	while let Some(button_a) = lines.next() {
+		check_position::<u32>(checker, button_a, "Button A: X+", "+")?;
+		let button_b = lines.next().ok_or_else(|| checker.error_at_end(button_a, ParseErrorKind::UnexpectedEnd))?;
+		check_position::<u32>(checker, button_b, "Button B: X+", "+")?;
+		let prize = lines.next().ok_or_else(|| checker.error_at_end(button_b, ParseErrorKind::UnexpectedEnd))?;
+		check_position::<u64>(checker, prize, "Prize: X=", "=")?;
+		
+		if let Some(separator) = lines.next() {
+			checker.end(separator)?;
+		}
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u128, ParseError> {
+	ClawMachine::try_parse_all(input).map(|machines| total_cost(&machines, Prices::DEFAULT, 0))
+}
+
 
 #[aoc(day13, part2)]
-pub fn part2(input: &str) -> u64 {
+pub fn part2(input: &str) -> u128 {
+	let machines = unsafe { ClawMachine::parse_all(input).unwrap_unchecked() };
+	total_cost(&machines, Prices::DEFAULT, PRIZE_OFFSET)
+}
+
+#[allow(unused)]
+pub fn part2_u64(input: &str) -> u64 {
 	let mut lines = input.lines();
 	
 	let mut tokens = 0;
 	loop {
 		match lines.next_chunk() {
 			Ok([button_a, button_b, prize]) => {
-				match (try_extract_pos(&button_a), try_extract_pos(&button_b), try_extract_pos(&prize)) {
+				match (try_extract_pos(&button_a), try_extract_pos(&button_b), try_extract_pos::<u64>(&prize)) {
 					(Ok(button_a), Ok(button_b), Ok(prize)) => {
-						let prize = Position { x: prize.x + 10000000000000, y: prize.y + 10000000000000 };
+						let prize = Position { x: prize.x + PRIZE_OFFSET, y: prize.y + PRIZE_OFFSET };
 						
 						tokens += tokens_to_win(button_a, button_b, prize).unwrap_or(0);
 					},
@@ -105,3 +284,53 @@ pub fn part2(input: &str) -> u64 {
 	
 	tokens
 }
+
+pub fn try_part2(input: &str) -> Result<u128, ParseError> {
+	ClawMachine::try_parse_all(input).map(|machines| total_cost(&machines, Prices::DEFAULT, PRIZE_OFFSET))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn machine(a: (u32, u32), b: (u32, u32), prize: (u64, u64)) -> ClawMachine {
+		ClawMachine { button_a: Position { x: a.0, y: a.1 }, button_b: Position { x: b.0, y: b.1 }, prize: Position { x: prize.0, y: prize.1 } }
+	}
+	
+	fn cheapest_by_search(machine: ClawMachine, prices: Prices) -> Option<u128> {
+		let (a, b, prize) = (machine.button_a, machine.button_b, machine.prize);
+		(0..=60u64).flat_map(|pa| (0..=60u64).map(move |pb| (pa, pb)))
+				.filter(|&(pa, pb)| pa * a.x as u64 + pb * b.x as u64 == prize.x && pa * a.y as u64 + pb * b.y as u64 == prize.y)
+				.map(|(pa, pb)| Presses { a: pa.into(), b: pb.into() }.cost(prices)).min()
+	}
+	
+	#[test]
+	fn example_machines() {
+		assert_eq!(machine((94, 34), (22, 67), (8400, 5400)).presses(Prices::DEFAULT, 0), Some(Presses { a: 80, b: 40 }));
+		assert_eq!(machine((26, 66), (67, 21), (12748, 12176)).presses(Prices::DEFAULT, 0), None);
+		assert!(machine((26, 66), (67, 21), (12748, 12176)).presses(Prices::DEFAULT, PRIZE_OFFSET).is_some());
+	}
+	
+	#[test]
+	fn collinear_buttons_pick_the_cheapest_presses() {
+		assert_eq!(machine((2, 2), (3, 3), (7, 7)).presses(Prices::DEFAULT, 0), Some(Presses { a: 2, b: 1 }));
+		assert_eq!(machine((2, 2), (3, 3), (7, 8)).presses(Prices::DEFAULT, 0), None);
+		assert_eq!(machine((3, 6), (1, 2), (9, 18)).presses(Prices::DEFAULT, 0), Some(Presses { a: 0, b: 9 }));
+		assert_eq!(machine((3, 6), (1, 2), (9, 18)).presses(Prices { a: 1, b: 5 }, 0), Some(Presses { a: 3, b: 0 }));
+	}
+	
+	#[test]
+	fn collinear_buttons_match_search() {
+		let buttons = [((1, 2), (2, 4)), ((3, 6), (1, 2)), ((2, 2), (3, 3)), ((4, 0), (6, 0)), ((0, 5), (0, 3)), ((0, 0), (1, 1)), ((0, 0), (0, 0))];
+		for (a, b) in buttons {
+			for prices in [Prices::DEFAULT, Prices { a: 1, b: 5 }, Prices { a: 2, b: 3 }] {
+				for px in 0..30 {
+					for py in 0..30 {
+						let machine = machine(a, b, (px, py));
+						assert_eq!(machine.cost(prices, 0), cheapest_by_search(machine, prices), "{machine:?} {prices:?}");
+					}
+				}
+			}
+		}
+	}
+}
@@ -1,5 +1,7 @@
 use std::{convert, iter, mem};
 
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
 #[derive(Copy, Clone)]
 struct Robot {
 	x: u32,
@@ -18,83 +20,151 @@ fn try_parse_robot(line: &str) -> Option<Robot> {
 
 const WIDTH: u32 = 101;
 const HEIGHT: u32 = 103;
+const STEPS: u32 = 100;
 
-#[aoc(day14, part1)]
-pub fn part1(input: &str) -> u32 {
-	let [mut tl, mut tr, mut bl, mut br] = [0u32; 4];
-	
-	for line in input.lines() {
-		let robot = unsafe { try_parse_robot(&line).unwrap_unchecked() };
-		
-		let end_x = (robot.x as i64 + (robot.vx as i64 * 100)).rem_euclid(WIDTH as i64) as u32;
-		let end_y = (robot.y as i64 + (robot.vy as i64 * 100)).rem_euclid(HEIGHT as i64) as u32;
+#[derive(Clone)]
+pub struct RobotField {
+	width: u32,
+	height: u32,
+	robots: Vec<Robot>
+}
+
+impl RobotField {
+	// Returns 'None' if any line isn't a robot, or if a robot starts outside the field.
+	pub fn parse(input: &str, width: u32, height: u32) -> Option<Self> {
+		assert!(width > 0 && height > 0, "field must not be empty");
 		
-		if end_x < WIDTH / 2 {
-			if end_y < HEIGHT / 2 {
-				tl += 1;
-			} else if end_y > HEIGHT / 2 {
-				bl += 1;
-			}
-		} else if end_x > WIDTH / 2 {
-			if end_y < HEIGHT / 2 {
-				tr += 1;
-			} else if end_y > HEIGHT / 2 {
-				br += 1;
-			}
+		let mut robots = Vec::with_capacity(500);
+		for line in input.lines() {
+			robots.push(try_parse_robot(line).filter(|r| r.x < width && r.y < height)?);
 		}
+		
+		Some(Self { width, height, robots })
 	}
 	
-	tl * tr * bl * br
-}
-
-
-#[aoc(day14, part2)]
-pub fn part2(input: &str) -> u32 {
-	let mut robots = Vec::with_capacity(500);
+	pub fn try_parse(input: &str, width: u32, height: u32) -> Result<Self, ParseError> {
+		check(input, width, height)?;
+		Ok(unsafe { Self::parse(input, width, height).unwrap_unchecked() })
+	}
 	
-	for line in input.lines() {
-		let robot = unsafe { try_parse_robot(&line).unwrap_unchecked() };
-		
-		robots.push(robot);
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+	
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+	
+	fn position_after(&self, robot: Robot, steps: u32) -> (u32, u32) {
+		let x = (robot.x as i64 + robot.vx as i64 * steps as i64).rem_euclid(self.width as i64) as u32;
+		let y = (robot.y as i64 + robot.vy as i64 * steps as i64).rem_euclid(self.height as i64) as u32;
+		(x, y)
 	}
 	
-	let mut picture = [[false; WIDTH as usize]; HEIGHT as usize];
+	pub fn positions_after(&self, steps: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+		map(&self.robots, move |&robot| self.position_after(robot, steps))
+	}
 	
-	for iteration in 3000.. {
-		let mut had_overlap = false;
-		for &robot in &robots {
-			let x = (robot.x as i64 + robot.vx as i64 * iteration as i64).rem_euclid(WIDTH as i64) as usize;
-			let y = (robot.y as i64 + robot.vy as i64 * iteration as i64).rem_euclid(HEIGHT as i64) as usize;
+	// Robots on the middle row or column (when there is one) aren't in any quadrant.
+	pub fn safety_factor(&self, steps: u32) -> u32 {
+		let [mut tl, mut tr, mut bl, mut br] = [0u32; 4];
+		let (mid_x, mid_y) = (self.width / 2, self.height / 2);
+		let on_middle = |end, mid, size| size % 2 == 1 && end == mid;
+		
+		for (end_x, end_y) in self.positions_after(steps) {
+			if on_middle(end_x, mid_x, self.width) || on_middle(end_y, mid_y, self.height) {
+				continue;
+			}
 			
-			if mem::replace(&mut picture[y][x], true) {
-				had_overlap = true;
-				break;
-			};
+			match (end_x < mid_x, end_y < mid_y) {
+				(true, true) => tl += 1,
+				(false, true) => tr += 1,
+				(true, false) => bl += 1,
+				(false, false) => br += 1
+			}
 		}
 		
-		if !had_overlap {
-			let mut enclosed = 0;
+		tl * tr * bl * br
+	}
+	
+	// Looks for the tree from step 'start' onwards, giving up once every arrangement has been seen.
+	pub fn first_tree(&self, start: u32) -> Option<u32> {
+		let (width, height) = (self.width as usize, self.height as usize);
+		let mut picture = vec![false; width * height];
+		
+		for iteration in start..start.saturating_add(self.width.saturating_mul(self.height)) {
+			let mut had_overlap = false;
+			for (x, y) in self.positions_after(iteration) {
+				if mem::replace(&mut picture[y as usize * width + x as usize], true) {
+					had_overlap = true;
+					break;
+				};
+			}
 			
-			// There are 7 characters between the edge of the tree's frame and the outermost "enclosed" pixel horizontally.
-			// There are actually only 6 vertically, but this'll still skip at most two of the 161 in the picture, which still leaves much more than 100.
-			// (Looking for 100 is probably overly conservative, too, seeing as the only time my input produces "enclosed" pixels at all is when the tree appears.)
-			for (row, contents) in enumerate(&picture[..(HEIGHT - 7) as usize]).skip(7) {
-				let adj_rows = [&picture[row - 1], &picture[row + 1]];
-				for col in enumerate(&contents[..(WIDTH - 7) as usize]).skip(7).filter_map(|(i, &b)| b.then(|| i)) {
-					if map([col - 1, col + 1], |i| contents[i]).chain(map(adj_rows, |r| r[col])).all(convert::identity) {
-						enclosed += 1;
-						if enclosed >= 100 { // Should be exactly 161 in tree.
-							return iteration;
+			if !had_overlap {
+				let mut enclosed = 0;
+				let rows: Vec<&[bool]> = picture.chunks_exact(width).collect();
+				
+				// There are 7 characters between the edge of the tree's frame and the outermost "enclosed" pixel horizontally.
+				// There are actually only 6 vertically, but this'll still skip at most two of the 161 in the picture, which still leaves much more than 100.
+				// (Looking for 100 is probably overly conservative, too, seeing as the only time my input produces "enclosed" pixels at all is when the tree appears.)
+				for (row, &contents) in enumerate(&rows[..height.saturating_sub(7)]).skip(7) {
+					let adj_rows = [rows[row - 1], rows[row + 1]];
+					for col in enumerate(&contents[..width.saturating_sub(7)]).skip(7).filter_map(|(i, &b)| b.then(|| i)) {
+						if map([col - 1, col + 1], |i| contents[i]).chain(map(adj_rows, |r| r[col])).all(convert::identity) {
+							enclosed += 1;
+							if enclosed >= 100 { // Should be exactly 161 in tree.
+								return Some(iteration);
+							}
 						}
 					}
 				}
 			}
+			
+			picture.fill(false);
 		}
 		
-		picture.as_flattened_mut().fill(false);
+		None
+	}
+}
+
+#[aoc(day14, part1)]
+pub fn part1(input: &str) -> u32 {
+	let field = unsafe { RobotField::parse(input, WIDTH, HEIGHT).unwrap_unchecked() };
+	field.safety_factor(STEPS)
+}
+
+fn check(input: &str, width: u32, height: u32) -> Result<(), ParseError> {
+	let checker = Checker::new(14, input);
+	for line in input.lines() {
+		let (p, v) = checker.split_once(checker.prefix(line, "p=")?, " v=")?;
+		let ((px, py), (vx, vy)) = (checker.split_once(p, ",")?, checker.split_once(v, ",")?);
+		for (value, bound) in [(px, width), (py, height)] {
+			if checker.number::<u32>(value)? >= bound {
+				return Err(checker.error_at(value, ParseErrorKind::OutOfRange));
+			}
+		}
+		checker.number::<i32>(vx)?;
+		checker.number::<i32>(vy)?;
 	}
 	
-	u32::MAX
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	RobotField::try_parse(input, WIDTH, HEIGHT).map(|field| field.safety_factor(STEPS))
+}
+
+
+#[aoc(day14, part2)]
+pub fn part2(input: &str) -> u32 {
+	let field = unsafe { RobotField::parse(input, WIDTH, HEIGHT).unwrap_unchecked() };
+	field.first_tree(3000).unwrap_or(u32::MAX)
+}
+
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+	let field = RobotField::try_parse(input, WIDTH, HEIGHT)?;
+	field.first_tree(3000).ok_or_else(|| Checker::new(14, input).error_at_end(input, ParseErrorKind::Unsolvable))
 }
 
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
@@ -105,3 +175,24 @@ fn map<I: IntoIterator, O, F: FnMut(I::Item) -> O>(i: I, f: F) -> iter::Map<I::I
 	i.into_iter().map(f)
 }
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn example() {
+		let input = "p=0,4 v=3,-3\np=6,3 v=-1,-3\np=10,3 v=-1,2\np=2,0 v=2,-1\np=0,0 v=1,3\np=3,0 v=-2,-2\np=7,6 v=-1,-3\np=3,0 v=-1,-2\np=9,3 v=2,3\np=7,3 v=-1,2\np=2,4 v=2,-3\np=9,5 v=-3,-3\n";
+		let field = RobotField::parse(input, 11, 7).unwrap();
+		assert_eq!(field.safety_factor(100), 12);
+	}
+	
+	#[test]
+	fn even_sized_field_has_no_middle() {
+		let input = "p=1,1 v=0,0\np=2,1 v=0,0\np=1,2 v=0,0\np=2,2 v=0,0\np=3,3 v=0,0\n";
+		assert_eq!(RobotField::parse(input, 4, 4).unwrap().safety_factor(0), 2);
+		
+		// The same robots on a 5 wide field, where column 2 is the middle.
+		assert_eq!(RobotField::parse(input, 5, 4).unwrap().safety_factor(0), 0);
+		assert_eq!(RobotField::parse("p=0,0 v=0,0\np=3,0 v=0,0\np=0,3 v=0,0\np=4,3 v=0,0\n", 5, 4).unwrap().safety_factor(0), 1);
+	}
+}
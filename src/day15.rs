@@ -1,5 +1,8 @@
 use std::{iter, num::NonZero};
 
+use crate::error::{Checker, ParseError};
+use crate::grid::Grid;
+
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum Space {
 	Empty,
@@ -15,131 +18,6 @@ enum Direction {
 	West
 }
 
-#[derive(Clone)]
-struct Grid<T> {
-	grid: Vec<T>,
-	row_length: NonZero<usize>
-}
-
-impl<T> Grid<T> {
-	#[allow(unused)]
-	pub fn from_vec(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
-		assert_eq!(grid.len() % row_length, 0);
-		assert!( !grid.is_empty() );
-		
-		unsafe { Self::from_vec_unchecked(grid, row_length) }
-	}
-	
-	#[allow(unused)]
-	pub fn from_first_row(grid: Vec<T>) -> Result<Self, Vec<T>> {
-		if let Some(row_length) = NonZero::new(grid.len()) {
-			Ok(unsafe { Self::from_vec_unchecked(grid, row_length) })
-		} else {
-			Err(grid)
-		}
-	}
-	
-	pub unsafe fn from_vec_unchecked(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
-		Self { grid, row_length }
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn as_vec_mut(&mut self) -> &mut Vec<T> {
-		&mut self.grid
-	}
-	
-	#[allow(unused)]
-	pub fn reserve_rows(&mut self, rows: usize) {
-		let capacity = self.row_length().get() * rows;
-		unsafe {
-			self.as_vec_mut().reserve(capacity);
-		}
-	}
-	
-	#[allow(unused)]
-	pub fn add_filled_row(&mut self, value: T) -> &mut [T] where T: Clone {
-		let prev_len = self.total_length().get();
-		let row_len = self.row_length().get();
-		unsafe {
-			self.as_vec_mut().resize(prev_len + row_len, value);
-			self.as_mut_slice().get_unchecked_mut(prev_len..)
-		}
-	}
-	
-	
-	pub fn as_slice(&self) -> &[T] {
-		&self.grid
-	}
-	
-	pub fn as_mut_slice(&mut self) -> &mut [T] {
-		&mut self.grid
-	}
-	
-	
-	pub fn get(&self, row: usize, col: usize) -> Option<&T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
-	}
-	
-	#[allow(unused)]
-	pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_mut_slice().get_unchecked_mut(i) })
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &T {
-		self.as_slice().get_unchecked(self.to_index_unchecked(row, col))
-	}
-	
-	pub unsafe fn get_unchecked_mut(&mut self, row: usize, col: usize) -> &mut T {
-		let index = self.to_index_unchecked(row, col);
-		self.as_mut_slice().get_unchecked_mut(index)
-	}
-	
-	
-	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
-		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
-	}
-	
-	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
-		row * self.row_length().get() + col
-	}
-	
-	#[allow(unused)]
-	pub fn to_row_col(&self, index: usize) -> Option<(usize, usize)> {
-		(index < self.total_length().get()).then(|| self.to_row_col_unchecked(index))
-	}
-	
-	pub fn to_row_col_unchecked(&self, index: usize) -> (usize, usize) {
-		(index / self.row_length(), index % self.row_length())
-	}
-	
-	
-	pub fn get_row(&self, row: usize) -> Option<&[T]> {
-		let row_start = row.checked_mul(self.row_length().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_slice().get(row_start..row_end)
-	}
-	
-	pub fn get_row_mut(&mut self, row: usize) -> Option<&mut [T]> {
-		let row_start = row.checked_mul(self.row_length().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_mut_slice().get_mut(row_start..row_end)
-	}
-	
-	
-	pub fn row_count(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.total_length().get() / self.row_length()) }
-	}
-	
-	pub fn row_length(&self) -> NonZero<usize> {
-		self.row_length
-	}
-	
-	pub fn total_length(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.as_slice().len()) }
-	}
-}
-
 fn wrapping_offset(position: (usize, usize), direction: Direction) -> (usize, usize) {
 	let (row, col) = position;
 	match direction {
@@ -223,10 +101,23 @@ pub fn part1(input: &str) -> u64 {
 			}
 		}
 	}
-			
+	
 	box_sum(&grid)
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(15, input);
+	let (grid, directions) = checker.split_once(input, "\n\n")?;
+	
+	checker.grid(grid, |b| matches!(b, b'#' | b'O' | b'.' | b'@'))?;
+	checker.unique(grid, b'@')?;
+	checker.bytes_in(directions, |b| matches!(b, b'^' | b'>' | b'v' | b'<' | b'\n'))
+}
+
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum Space2 {
 	Empty,
@@ -395,10 +286,14 @@ pub fn part2(input: &str) -> u64 {
 			}
 		}
 	}
-			
+	
 	box_sum2(&grid)
 }
 
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
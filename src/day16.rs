@@ -2,8 +2,10 @@ use std::{cmp::{self, Reverse}, collections::{BinaryHeap, /* HashMap, HashSet */
 
 use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet, FxBuildHasher};
 
-#[derive(Copy, Eq, Hash, Clone, PartialEq)]
-enum Direction {
+use crate::{error::{Checker, ParseError, ParseErrorKind}, grid::Grid};
+
+#[derive(Copy, Eq, Hash, Clone, PartialEq, Debug)]
+pub enum Direction {
 	North,
 	East,
 	South,
@@ -28,8 +30,19 @@ impl Direction {
 			Self::West => Self::South
 		}
 	}
+	
+	pub fn arrow(self) -> u8 {
+		match self {
+			Self::North => b'^',
+			Self::East => b'>',
+			Self::South => b'v',
+			Self::West => b'<'
+		}
+	}
 }
 
+const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
 #[derive(Copy, Eq, Hash, Clone, PartialEq)]
 struct Reindeer {
 	pub position: (usize, usize),
@@ -112,8 +125,247 @@ fn go(mut passable: impl FnMut((usize, usize)) -> bool, start: Reindeer, end: (u
 	None
 }
 
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct CostModel {
+	pub step: u64,
+	pub turn: u64
+}
+
+impl CostModel {
+	pub const DEFAULT: Self = Self { step: 1, turn: 1000 };
+	
+	// Turning to face the opposite way takes two turns.
+	fn to_move(self, from: Direction, to: Direction) -> u64 {
+		let turns = match (to as u8).wrapping_sub(from as u8) % 4 {
+			0 => 0,
+			2 => 2,
+			_ => 1
+		};
+		
+		self.step + turns * self.turn
+	}
+}
+
+#[derive(Clone)]
+pub struct ReindeerMaze {
+	tiles: Grid<u8>,
+	start: (usize, usize),
+	end: (usize, usize)
+}
+
+impl ReindeerMaze {
+	pub fn parse(input: &str) -> Option<Self> {
+		let mut rows = input.lines();
+		let mut tiles = Grid::from_first_row(rows.next()?.as_bytes().to_vec()).ok()?;
+		for row in rows {
+			let row = row.as_bytes();
+			(row.len() == tiles.row_length().get()).then(|| tiles.add_filled_row(b'#').copy_from_slice(row))?;
+		}
+		
+		let find = |marker| tiles.as_slice().iter().position(|&b| b == marker).map(|i| tiles.to_row_col_unchecked(i));
+		let (start, end) = (find(b'S')?, find(b'E')?);
+		Some(Self { tiles, start, end })
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+	
+	pub fn start(&self) -> (usize, usize) {
+		self.start
+	}
+	
+	pub fn end(&self) -> (usize, usize) {
+		self.end
+	}
+	
+	// States are a tile and the direction the reindeer is facing on it.
+	fn state(&self, position: (usize, usize), facing: Direction) -> usize {
+		self.tiles.to_index_unchecked(position.0, position.1) * 4 + facing as usize
+	}
+	
+	fn unpack(&self, state: usize) -> ((usize, usize), Direction) {
+		(self.tiles.to_row_col_unchecked(state / 4), DIRECTIONS[state % 4])
+	}
+	
+	// Where the reindeer ends up by stepping 'towards' (turning first if needed), if that isn't a wall.
+	fn step(&self, state: usize, towards: Direction) -> Option<usize> {
+		let (row, col) = wrapping_offset(self.unpack(state).0, towards);
+		self.tiles.get(row, col).filter(|&&tile| tile != b'#').map(|_| self.state((row, col), towards))
+	}
+	
+	// Returns 'None' if the end can't be reached. Panics if steps are free, since there'd be no end to the optimal paths.
+	pub fn solve(&self, costs: CostModel) -> Option<Solution<'_>> {
+		assert!(costs.step > 0, "steps must have a cost");
+		
+		let mut scores = vec![u64::MAX; self.tiles.total_length().get() * 4];
+		let mut queue = BinaryHeap::with_capacity(1024);
+		let start = self.state(self.start, Direction::East);
+		scores[start] = 0;
+		queue.push(Reverse((0, start)));
+		
+		while let Some(Reverse((score, state))) = queue.pop() {
+			if score > scores[state] {
+				continue;
+			}
+			
+			let facing = self.unpack(state).1;
+			for towards in DIRECTIONS {
+				if let Some(next) = self.step(state, towards) {
+					let score = score + costs.to_move(facing, towards);
+					if score < scores[next] {
+						scores[next] = score;
+						queue.push(Reverse((score, next)));
+					}
+				}
+			}
+		}
+		
+		let end_states = DIRECTIONS.map(|facing| self.state(self.end, facing));
+		let score = end_states.iter().map(|&state| scores[state]).min().filter(|&score| score != u64::MAX)?;
+		
+		// Work backwards from the end, only following moves that were part of a cheapest way to get where they lead.
+		let mut optimal = vec![false; scores.len()];
+		let mut stack: Vec<_> = end_states.into_iter().filter(|&state| scores[state] == score).collect();
+		for &state in &stack {
+			optimal[state] = true;
+		}
+		
+		while let Some(state) = stack.pop() {
+			let (position, facing) = self.unpack(state);
+			let (row, col) = wrapping_offset(position, facing.turned_clockwise().turned_clockwise());
+			let Some(index) = self.tiles.to_index(row, col) else { continue };
+			for from in DIRECTIONS {
+				let previous = index * 4 + from as usize;
+				if !optimal[previous] && scores[previous] != u64::MAX && scores[previous] + costs.to_move(from, facing) == scores[state] {
+					optimal[previous] = true;
+					stack.push(previous);
+				}
+			}
+		}
+		
+		Some(Solution { maze: self, costs, score, scores, optimal })
+	}
+}
+
+pub struct Solution<'a> {
+	maze: &'a ReindeerMaze,
+	costs: CostModel,
+	score: u64,
+	scores: Vec<u64>,
+	optimal: Vec<bool>
+}
+
+impl Solution<'_> {
+	pub fn score(&self) -> u64 {
+		self.score
+	}
+	
+	// In reading order.
+	pub fn tiles(&self) -> Vec<(usize, usize)> {
+		let tiles = self.optimal.chunks_exact(4).enumerate().filter(|(_, facings)| facings.contains(&true));
+		map(tiles, |(i, _)| self.maze.tiles.to_row_col_unchecked(i)).collect()
+	}
+	
+	pub fn tile_count(&self) -> usize {
+		self.optimal.chunks_exact(4).filter(|facings| facings.contains(&true)).count()
+	}
+	
+	fn next_on_path(&self, state: usize, towards: Direction) -> Option<usize> {
+		let cost = self.costs.to_move(self.maze.unpack(state).1, towards);
+		self.maze.step(state, towards).filter(|&next| self.optimal[next] && self.scores[state] + cost == self.scores[next])
+	}
+	
+	// Each path is the direction of every step taken, with turns happening wherever the direction changes.
+	pub fn paths(&self) -> OptimalPaths<'_> {
+		OptimalPaths { solution: self, stack: vec![(self.maze.state(self.maze.start, Direction::East), 0)], path: Vec::new() }
+	}
+	
+	pub fn path(&self) -> Vec<Direction> {
+		self.paths().next().expect("a solution always has at least one optimal path")
+	}
+	
+	// Marks outside of the maze are skipped.
+	fn render_with(&self, marks: impl IntoIterator<Item = ((usize, usize), u8)>) -> String {
+		let mut tiles = self.maze.tiles.clone();
+		for ((row, col), mark) in marks {
+			let Some(tile) = tiles.get_mut(row, col) else { continue };
+			if !matches!(tile, b'S' | b'E') {
+				*tile = mark;
+			}
+		}
+		
+		let mut rendered = String::with_capacity(tiles.total_length().get() + tiles.row_count().get());
+		for row in tiles.rows() {
+			rendered.extend(row.iter().map(|&b| b as char));
+			rendered.push('\n');
+		}
+		rendered
+	}
+	
+	// The maze with every tile on some optimal path marked with 'O'.
+	pub fn render(&self) -> String {
+		self.render_with(map(self.tiles(), |position| (position, b'O')))
+	}
+	
+	// The maze with an arrow on each tile 'path' leaves from. 'path' doesn't have to be optimal, or even stay within the maze.
+	pub fn render_path(&self, path: &[Direction]) -> String {
+		let mut position = self.maze.start;
+		self.render_with(map(path, |&towards| {
+			let from = position;
+			position = wrapping_offset(position, towards);
+			(from, towards.arrow())
+		}))
+	}
+}
+
+pub struct OptimalPaths<'a> {
+	solution: &'a Solution<'a>,
+	// Every state along the current path, along with how many directions have been tried from it so far.
+	stack: Vec<(usize, u8)>,
+	path: Vec<Direction>
+}
+
+impl Iterator for OptimalPaths<'_> {
+	type Item = Vec<Direction>;
+	
+	fn next(&mut self) -> Option<Vec<Direction>> {
+		while let Some((state, tried)) = self.stack.last_mut() {
+			let Some(&towards) = DIRECTIONS.get(*tried as usize) else {
+				self.stack.pop();
+				self.path.pop();
+				continue;
+			};
+			
+			*tried += 1;
+			if let Some(next) = self.solution.next_on_path(*state, towards) {
+				self.path.push(towards);
+				if self.solution.maze.unpack(next).0 == self.solution.maze.end {
+					let path = self.path.clone();
+					self.path.pop();
+					return Some(path);
+				}
+				self.stack.push((next, 0));
+			}
+		}
+		
+		None
+	}
+}
+
 #[aoc(day16, part1)]
-pub fn part1(input: &str) -> u32 {
+pub fn part1(input: &str) -> u64 {
+	let maze = unsafe { ReindeerMaze::parse(input).unwrap_unchecked() };
+	maze.solve(CostModel::DEFAULT).unwrap().score()
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> u32 {
+	lowest_score(input).unwrap()
+}
+
+fn lowest_score(input: &str) -> Option<u32> {
 	let row_length = NonZero::new(input.find('\n').unwrap_or(input.len()) + 1).unwrap();
 	let start = input.find('S').map(|i| (i / row_length, i % row_length)).unwrap();
 	let end = input.find('E').map(|i| (i / row_length, i % row_length)).unwrap();
@@ -123,7 +375,39 @@ pub fn part1(input: &str) -> u32 {
 	// the newline characters, overflow off the top/left, etc. (or just flat out U.B. since switching to 'get_unchecked').
 	//                                   (using 'get_unchecked' seems to barely affect the runtime whatsoever btw) ^
 	go(|(row, col)| *unsafe{ input.get_unchecked(row * row_length.get() + col) } != b'#', Reindeer::from_position(start), end,
-			&mut BinaryHeap::with_capacity(256), &mut HashSet::with_capacity_and_hasher(20000, FxBuildHasher)).unwrap()
+			&mut BinaryHeap::with_capacity(256), &mut HashSet::with_capacity_and_hasher(20000, FxBuildHasher))
+}
+
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(16, input);
+	let grid = checker.grid(input, |b| matches!(b, b'#' | b'.' | b'S' | b'E'))?;
+	checker.unique(input, b'S')?;
+	checker.unique(input, b'E')?;
+	
+	// Both parts rely on the maze being surrounded by walls rather than doing any bounds checks.
+	let (last_row, last_col) = (grid.row_count().get() - 1, grid.row_length().get() - 1);
+	for (row, contents) in grid.rows().enumerate() {
+		let open = if row == 0 || row == last_row {
+			contents.iter().position(|&b| b != b'#')
+		} else {
+			[0, last_col].into_iter().find(|&col| contents[col] != b'#')
+		};
+		
+		if let Some(col) = open {
+			return Err(checker.error_at_offset(grid.to_index_unchecked(row, col), ParseErrorKind::Expected(b'#')));
+		}
+	}
+	
+	Ok(())
+}
+
+fn unsolvable(input: &str) -> ParseError {
+	Checker::new(16, input).error_at_offset(input.find('E').unwrap_or_default(), ParseErrorKind::Unsolvable)
+}
+
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+	let maze = ReindeerMaze::try_parse(input)?;
+	maze.solve(CostModel::DEFAULT).map(|solution| solution.score()).ok_or_else(|| unsolvable(input))
 }
 
 struct ReindeerState2 {
@@ -208,19 +492,88 @@ fn go2(mut passable: impl FnMut((usize, usize)) -> bool, start: Reindeer, end: (
 	
 	// eprintln!("[i] Queue capacity: {} / Visited set entries: {}", buffer.capacity(), visited_buffer.len());
 	best_visiteds.as_ref().map(HashSet::len)
-} 
+}
 
 #[aoc(day16, part2)]
 pub fn part2(input: &str) -> usize {
+	let maze = unsafe { ReindeerMaze::parse(input).unwrap_unchecked() };
+	maze.solve(CostModel::DEFAULT).unwrap().tile_count()
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> usize {
+	best_path_tiles(input).unwrap()
+}
+
+fn best_path_tiles(input: &str) -> Option<usize> {
 	let row_length = NonZero::new(input.find('\n').unwrap_or(input.len()) + 1).unwrap();
 	let start = input.find('S').map(|i| (i / row_length, i % row_length)).unwrap();
 	let end = input.find('E').map(|i| (i / row_length, i % row_length)).unwrap();
 	
 	let input = input.as_bytes(); // Could probably at least prune dead ends first to make this a fair bit faster. Unfortunately, I am lazy.
 	go2(|(row, col)| *unsafe{ input.get_unchecked(row * row_length.get() + col) } != b'#', Reindeer::from_position(start), end,
-			&mut BinaryHeap::with_capacity(1024), &mut HashMap::with_capacity_and_hasher(20000, FxBuildHasher)).unwrap()
+			&mut BinaryHeap::with_capacity(1024), &mut HashMap::with_capacity_and_hasher(20000, FxBuildHasher))
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+	let maze = ReindeerMaze::try_parse(input)?;
+	maze.solve(CostModel::DEFAULT).map(|solution| solution.tile_count()).ok_or_else(|| unsolvable(input))
 }
 
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
+
+fn map<I: IntoIterator, O, F: FnMut(I::Item) -> O>(i: I, f: F) -> iter::Map<I::IntoIter, F> {
+	i.into_iter().map(f)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "\
+###############
+#.......#....E#
+#.#.###.#.###.#
+#.....#.#...#.#
+#.###.#####.#.#
+#.#.#.......#.#
+#.#.#####.###.#
+#...........#.#
+###.#.#####.#.#
+#...#.....#.#.#
+#.#.#.###.#.#.#
+#.....#...#.#.#
+#.###.#.#.#.#.#
+#S..#.....#...#
+###############
+";
+
+	#[test]
+	fn example() {
+		let maze = ReindeerMaze::parse(EXAMPLE).unwrap();
+		let solution = maze.solve(CostModel::DEFAULT).unwrap();
+		assert_eq!((solution.score(), solution.tile_count()), (7036, 45));
+		assert_eq!(solution.paths().count(), 3);
+		
+		let path = solution.path();
+		let facings: Vec<_> = iter::once(Direction::East).chain(path.iter().copied()).collect();
+		let turns = facings.windows(2).filter(|pair| pair[0] != pair[1]).count() as u64;
+		assert_eq!(path.len() as u64 + turns * 1000, 7036);
+	}
+	
+	#[test]
+	fn render_path_leaving_the_maze() {
+		let maze = ReindeerMaze::parse("#####\n#S.E#\n#####\n").unwrap();
+		let solution = maze.solve(CostModel::DEFAULT).unwrap();
+		assert_eq!(solution.render_path(&solution.path()), "#####\n#S>E#\n#####\n");
+		
+		// Up off the top (through the wall), along outside the maze, and back onto it.
+		let wandering = [Direction::North, Direction::North, Direction::East, Direction::South, Direction::South];
+		assert_eq!(solution.render_path(&wandering), "#^v##\n#S.E#\n#####\n");
+		
+		let off_the_bottom = [Direction::South, Direction::South, Direction::South, Direction::South];
+		assert_eq!(solution.render_path(&off_the_bottom), "#####\n#S.E#\n#v###\n");
+	}
+}
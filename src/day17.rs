@@ -1,4 +1,6 @@
-use std::iter;
+use std::{fmt, iter};
+
+use crate::error::{Checker, ParseError, ParseErrorKind};
 
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum ThreeBit {
@@ -12,9 +14,9 @@ enum ThreeBit {
 	Seven
 }
 
-impl std::fmt::Display for ThreeBit {
-	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		std::fmt::Write::write_char(f, match self {
+impl fmt::Display for ThreeBit {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Write::write_char(f, match self {
 			Self::Zero => '0',
 			Self::One => '1',
 			Self::Two => '2',
@@ -60,6 +62,32 @@ impl ThreeBit {
 		u8::try_from(c).ok().and_then(Self::from_ascii)
 	}
 	
+	pub fn mnemonic(self) -> &'static str {
+		match self {
+			Self::Zero => "adv",
+			Self::One => "bxl",
+			Self::Two => "bst",
+			Self::Three => "jnz",
+			Self::Four => "bxc",
+			Self::Five => "out",
+			Self::Six => "bdv",
+			Self::Seven => "cdv"
+		}
+	}
+	
+	pub fn combo_name(self) -> &'static str {
+		match self {
+			Self::Zero => "0",
+			Self::One => "1",
+			Self::Two => "2",
+			Self::Three => "3",
+			Self::Four => "A",
+			Self::Five => "B",
+			Self::Six => "C",
+			Self::Seven => "<reserved>"
+		}
+	}
+	
 	pub fn evaluate_as_combo(self, registers: &[u64; 3]) -> Option<u64> {
 		Some(match self {
 			Self::Zero => 0,
@@ -76,11 +104,8 @@ impl ThreeBit {
 	fn evaluate_div(operand: Self, registers: &[u64; 3]) -> Result<u64, InstrError> {
 		let numerator = registers[0];
 		let shift = operand.evaluate_as_combo(registers).ok_or(InstrError::Reserved)?;
-		Ok(if shift < 32 {
-			numerator >> shift
-		} else {
-			0
-		})
+		// Shifting out every bit leaves nothing.
+		Ok(u32::try_from(shift).ok().and_then(|shift| numerator.checked_shr(shift)).unwrap_or(0))
 	}
 	
 	pub fn evaluate_as_instruction_with(self, operand: Self, registers: &mut [u64; 3]) -> Result<Continuation, InstrError> {
@@ -126,42 +151,262 @@ impl ThreeBit {
 	}
 }
 
-#[aoc(day17, part1)]
-pub fn part1(input: &str) -> String {
-	const VALUE_AT: usize = "Register A: ".len();
-	let (a, remainder) = input[VALUE_AT..].split_once('\n').unwrap();
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum ExecError {
+	ReservedOperand{ instruction_pointer: usize }
+}
+
+impl fmt::Display for ExecError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Self::ReservedOperand{ instruction_pointer } => write!(f, "reserved combo operand 7 used at {instruction_pointer}")
+		}
+	}
+}
+
+// One executed instruction, with the registers as they were before it ran.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct TraceEntry {
+	pub instruction_pointer: usize,
+	pub opcode: u8,
+	pub operand: u8,
+	pub registers: [u64; 3],
+	pub output: Option<u8>
+}
+
+impl fmt::Display for TraceEntry {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let [a, b, c] = self.registers;
+		write!(f, "{:>3}: {:<14} A={a} B={b} C={c}", self.instruction_pointer, Instruction(self.opcode, self.operand).to_string())?;
+		match self.output {
+			Some(value) => write!(f, " -> {value}"),
+			None => Ok(())
+		}
+	}
+}
+
+struct Instruction(u8, u8);
+
+impl fmt::Display for Instruction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let (Some(opcode), Some(operand)) = (ThreeBit::from_value(self.0), ThreeBit::from_value(self.1)) else {
+			return write!(f, "??? {},{}", self.0, self.1);
+		};
+		
+		match opcode {
+			ThreeBit::One | ThreeBit::Three => write!(f, "{} {operand}", opcode.mnemonic()),
+			ThreeBit::Four => write!(f, "{}", opcode.mnemonic()), // Operand is read but ignored.
+			_ => write!(f, "{} {}", opcode.mnemonic(), operand.combo_name())
+		}
+	}
+}
+
+// Renders one instruction per line, prefixed with its address. A trailing opcode without an operand halts the machine.
+pub fn disassemble(program: &[u8]) -> String {
+	use fmt::Write as _;
 	
-	let mut registers = [a.parse().unwrap(), 0, 0];
+	let mut out = String::with_capacity(program.len() * 8);
+	for (i, pair) in enumerate(program.chunks(2)) {
+		let _ = match *pair {
+			[opcode, operand] => writeln!(out, "{:>3}: {}", i * 2, Instruction(opcode, operand)),
+			[opcode] => writeln!(out, "{:>3}: {opcode} (no operand; halts)", i * 2),
+			_ => unreachable!()
+		};
+	}
 	
-	let mut program = Vec::with_capacity(16);
-	program.extend(
-		remainder.split_once('P').unwrap().1["rogram: ".len()..]
-			.split(',').map(str::trim).map(str::parse).map(Result::unwrap)
-			.map(ThreeBit::from_char).map(Option::unwrap)
-	);
+	out
+}
+
+#[derive(Clone)]
+pub struct Computer {
+	registers: [u64; 3],
+	program: Vec<ThreeBit>,
+	instruction_pointer: usize,
+	output: Vec<ThreeBit>,
+	trace: Option<Vec<TraceEntry>>
+}
+
+impl Computer {
+	// Returns 'None' if any program value isn't three bits.
+	pub fn new(registers: [u64; 3], program: &[u8]) -> Option<Self> {
+		let program = program.iter().map(|&v| ThreeBit::from_value(v)).collect::<Option<_>>()?;
+		Some(Self { registers, program, instruction_pointer: 0, output: Vec::with_capacity(20), trace: None })
+	}
+	
+	pub fn parse(input: &str) -> Option<Self> {
+		let mut lines = input.lines();
+		let mut registers = [0; 3];
+		for (register, prefix) in iter::zip(&mut registers, ["Register A: ", "Register B: ", "Register C: "]) {
+			*register = lines.next()?.strip_prefix(prefix)?.parse().ok()?;
+		}
+		
+		let program = lines.find(|line| !line.is_empty())?.strip_prefix("Program: ")?;
+		let program = program.split(',').map(|v| v.trim().parse().ok()).collect::<Option<Vec<u8>>>()?;
+		
+		Self::new(registers, &program)
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+	
+	// Starts recording a 'TraceEntry' for every instruction executed from now on.
+	pub fn with_trace(mut self) -> Self {
+		self.trace = Some(Vec::new());
+		self
+	}
+	
+	pub fn trace(&self) -> &[TraceEntry] {
+		self.trace.as_deref().unwrap_or_default()
+	}
+	
+	pub fn registers(&self) -> [u64; 3] {
+		self.registers
+	}
+	
+	pub fn instruction_pointer(&self) -> usize {
+		self.instruction_pointer
+	}
+	
+	pub fn program(&self) -> impl Iterator<Item = u8> + '_ {
+		self.program.iter().map(|&v| v as u8)
+	}
+	
+	pub fn output(&self) -> impl Iterator<Item = u8> + '_ {
+		self.output.iter().map(|&v| v as u8)
+	}
+	
+	pub fn output_string(&self) -> String {
+		let mut output = String::with_capacity(self.output.len() * 2);
+		for &val in &self.output {
+			if !output.is_empty() {
+				output.push(',');
+			}
+			output.push((b'0' + val as u8) as char);
+		}
+		
+		output
+	}
+	
+	pub fn disassemble(&self) -> String {
+		disassemble(&self.program().collect::<Vec<_>>())
+	}
 	
-	let mut output = String::with_capacity(20);
-	let mut index = 0;
-	loop {
-		let Some([opcode, operand]) = program.get(index..index + 2).map(|slice| <[ThreeBit; 2]>::try_from(slice).unwrap()) else {
-			break output;
+	pub fn is_halted(&self) -> bool {
+		self.program.get(self.instruction_pointer..self.instruction_pointer + 2).is_none()
+	}
+	
+	// Puts the machine back at the start of its program with the given registers, clearing the output and trace.
+	pub fn reset(&mut self, registers: [u64; 3]) {
+		self.registers = registers;
+		self.instruction_pointer = 0;
+		self.output.clear();
+		if let Some(trace) = &mut self.trace {
+			trace.clear();
+		}
+	}
+	
+	// Executes one instruction. Returns 'Ok(false)' (without doing anything) if the machine has halted.
+	pub fn step(&mut self) -> Result<bool, ExecError> {
+		let Some(&[opcode, operand]) = self.program.get(self.instruction_pointer..self.instruction_pointer + 2) else {
+			return Ok(false);
 		};
 		
-		match opcode.evaluate_as_instruction_with(operand, &mut registers) {
-			Ok(Continuation::Continue) => index += 2,
-			Ok(Continuation::JumpTo(i)) => index = i as usize,
+		let (instruction_pointer, registers) = (self.instruction_pointer, self.registers);
+		let output = match opcode.evaluate_as_instruction_with(operand, &mut self.registers) {
+			Ok(Continuation::Continue) => {
+				self.instruction_pointer += 2;
+				None
+			},
+			Ok(Continuation::JumpTo(i)) => {
+				self.instruction_pointer = i as usize;
+				None
+			},
 			Ok(Continuation::Output(val)) => {
-				if !output.is_empty() {
-					output.reserve(2);
-					output.push(',');
-				}
-				output.push((b'0' + val as u8) as char);
-				
-				index += 2;
+				self.output.push(val);
+				self.instruction_pointer += 2;
+				Some(val as u8)
 			},
-			Err(_) => unreachable!()
+			Err(InstrError::Reserved) => return Err(ExecError::ReservedOperand{ instruction_pointer })
+		};
+		
+		if let Some(trace) = &mut self.trace {
+			trace.push(TraceEntry { instruction_pointer, opcode: opcode as u8, operand: operand as u8, registers, output });
+		}
+		
+		Ok(true)
+	}
+	
+	// Note that this never returns for programs that loop forever; see 'run_for'.
+	pub fn run(&mut self) -> Result<(), ExecError> {
+		while self.step()? {}
+		Ok(())
+	}
+	
+	// Executes at most 'max_steps' instructions. Returns whether the machine has halted.
+	pub fn run_for(&mut self, max_steps: usize) -> Result<bool, ExecError> {
+		for _ in 0..max_steps {
+			if !self.step()? {
+				return Ok(true);
+			}
+		}
+		
+		Ok(self.is_halted())
+	}
+}
+
+#[aoc(day17, part1)]
+pub fn part1(input: &str) -> String {
+	let mut computer = Computer::parse(input).unwrap();
+	match computer.run() {
+		Ok(()) => computer.output_string(),
+		Err(_) => unreachable!()
+	}
+}
+
+// Returns the list of comma-separated program values.
+fn check(input: &str) -> Result<&str, ParseError> {
+	let checker = Checker::new(17, input);
+	let mut rest = input;
+	for register in ["Register A: ", "Register B: ", "Register C: "] {
+		let (value, after) = checker.split_once(checker.prefix(rest, register)?, "\n")?;
+		checker.number::<u64>(value)?;
+		rest = after;
+	}
+	
+	let program = checker.prefix(rest, "\nProgram: ")?.trim_end();
+	let mut values = program.split(',');
+	while let Some(opcode) = values.next() {
+		let operand = values.next().ok_or_else(|| checker.error_at_end(program, ParseErrorKind::UnexpectedEnd))?;
+		for value in [opcode, operand] {
+			match *value.as_bytes() {
+				[b'0'..=b'7'] => {},
+				[] => return Err(checker.error_at(value, ParseErrorKind::UnexpectedEnd)),
+				[b'0'..=b'7', b, ..] => return Err(checker.error_at(&value[1..], ParseErrorKind::UnexpectedByte(b))),
+				[b, ..] => return Err(checker.error_at(value, ParseErrorKind::UnexpectedByte(b)))
+			}
+		}
+		
+		// Combo operand 7 is reserved, and all of 'adv', 'bst', 'out', 'bdv' and 'cdv' take a combo operand.
+		if operand == "7" && matches!(opcode, "0" | "2" | "5" | "6" | "7") {
+			return Err(checker.error_at(operand, ParseErrorKind::Unsupported));
 		}
 	}
+	
+	Ok(program)
+}
+
+// Far more than any program that shifts 'A' down each time round its loop can take, even starting from 'u64::MAX'.
+const STEP_LIMIT: usize = 1 << 20;
+
+pub fn try_part1(input: &str) -> Result<String, ParseError> {
+	let mut computer = Computer::try_parse(input)?;
+	match computer.run_for(STEP_LIMIT) {
+		Ok(true) => Ok(computer.output_string()),
+		Ok(false) => Err(Checker::new(17, input).error_at_end(input, ParseErrorKind::Unsolvable)),
+		Err(_) => unreachable!() // 'check' rejects reserved combo operands.
+	}
 }
 
 // ungodly abomination that implements Backtracking™.
@@ -213,8 +458,194 @@ fn test_thing(program: &[ThreeBit], /* initial_registers: [u64; 3], */ remaining
 }
 
 
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum QuineError {
+	NotThreeBit,
+	OddLength,
+	ReservedOperand{ instruction_pointer: usize },
+	NotSingleLoop, // The only jump must be a 'jnz 0' at the very end.
+	OutputsPerIteration(usize),
+	ShiftsPerIteration(usize),
+	ShiftNotLiteral{ instruction_pointer: usize }, // 'adv' has to shift by a constant 1, 2 or 3.
+	CarriedRegister{ instruction_pointer: usize, register: char }, // B or C is read before being written, so carries over between iterations.
+	TooLong, // The smallest A with that many iterations doesn't fit in a 'u64'.
+	NoSolution
+}
+
+impl fmt::Display for QuineError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Self::NotThreeBit => write!(f, "program contains a value above 7"),
+			Self::OddLength => write!(f, "program has an opcode without an operand"),
+			Self::ReservedOperand{ instruction_pointer } => write!(f, "reserved combo operand 7 used at {instruction_pointer}"),
+			Self::NotSingleLoop => write!(f, "program isn't a single loop ending in 'jnz 0'"),
+			Self::OutputsPerIteration(n) => write!(f, "loop outputs {n} values per iteration instead of 1"),
+			Self::ShiftsPerIteration(n) => write!(f, "loop shifts A {n} times per iteration instead of once"),
+			Self::ShiftNotLiteral{ instruction_pointer } => write!(f, "shift at {instruction_pointer} isn't by a constant 1, 2 or 3"),
+			Self::CarriedRegister{ instruction_pointer, register } => write!(f, "register {register} is read at {instruction_pointer} before being written"),
+			Self::TooLong => write!(f, "output too long for A to fit in 64 bits"),
+			Self::NoSolution => write!(f, "no value of A produces the output")
+		}
+	}
+}
+
+// What 'analyse' found out about a program: each iteration of its loop outputs a single value that depends only on A,
+// then shifts A right by 'shift' bits.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct LoopShape {
+	pub shift: u32
+}
+
+pub fn analyse(program: &[u8]) -> Result<LoopShape, QuineError> {
+	let program = program.iter().map(|&v| ThreeBit::from_value(v)).collect::<Option<Vec<_>>>().ok_or(QuineError::NotThreeBit)?;
+	analyse_three_bit(&program)
+}
+
+fn analyse_three_bit(program: &[ThreeBit]) -> Result<LoopShape, QuineError> {
+	let (pairs, []) = program.as_chunks::<2>() else {
+		return Err(QuineError::OddLength);
+	};
+	let Some((&[ThreeBit::Three, ThreeBit::Zero], body)) = pairs.split_last() else {
+		return Err(QuineError::NotSingleLoop);
+	};
+	
+	let (mut outputs, mut shifts, mut shift) = (0, 0, 0);
+	let mut written = [true, false, false]; // A is the only register that carries between iterations.
+	for (i, &[opcode, operand]) in enumerate(body) {
+		let instruction_pointer = i * 2;
+		let read = |written: &[bool; 3], register: usize| match written[register] {
+			true => Ok(()),
+			false => Err(QuineError::CarriedRegister{ instruction_pointer, register: (b'A' + register as u8) as char })
+		};
+		let read_combo = |written: &[bool; 3]| match operand {
+			ThreeBit::Seven => Err(QuineError::ReservedOperand{ instruction_pointer: instruction_pointer + 1 }),
+			ThreeBit::Four | ThreeBit::Five | ThreeBit::Six => read(written, operand as usize - 4),
+			_ => Ok(())
+		};
+		
+		match opcode {
+			ThreeBit::Zero => { // adv
+				if !matches!(operand, ThreeBit::One | ThreeBit::Two | ThreeBit::Three) {
+					read_combo(&written)?;
+					return Err(QuineError::ShiftNotLiteral{ instruction_pointer });
+				}
+				shifts += 1;
+				shift = operand as u32;
+			},
+			ThreeBit::One => read(&written, 1)?, // bxl
+			ThreeBit::Two => { // bst
+				read_combo(&written)?;
+				written[1] = true;
+			},
+			ThreeBit::Three => return Err(QuineError::NotSingleLoop),
+			ThreeBit::Four => { // bxc
+				read(&written, 1)?;
+				read(&written, 2)?;
+			},
+			ThreeBit::Five => { // out
+				read_combo(&written)?;
+				outputs += 1;
+			},
+			ThreeBit::Six | ThreeBit::Seven => { // bdv, cdv
+				read_combo(&written)?;
+				written[opcode as usize - 5] = true;
+			}
+		}
+	}
+	
+	match (outputs, shifts) {
+		(1, 1) => Ok(LoopShape { shift }),
+		(1, n) => Err(QuineError::ShiftsPerIteration(n)),
+		(n, _) => Err(QuineError::OutputsPerIteration(n))
+	}
+}
+
+// Runs a single iteration of a loop body (as accepted by 'analyse_three_bit') with the given value of A.
+fn iteration_output(body: &[ThreeBit], a: u64) -> ThreeBit {
+	let mut registers = [a, 0, 0];
+	for &[opcode, operand] in body.as_chunks::<2>().0 {
+		if let Ok(Continuation::Output(val)) = opcode.evaluate_as_instruction_with(operand, &mut registers) {
+			return val;
+		}
+	}
+	
+	unreachable!()
+}
+
+// Finds the smallest initial value of A (with B and C irrelevant) that makes 'program' output exactly 'target'.
+pub fn find_input_for(program: &[u8], target: &[u8]) -> Result<u64, QuineError> {
+	let program = program.iter().map(|&v| ThreeBit::from_value(v)).collect::<Option<Vec<_>>>().ok_or(QuineError::NotThreeBit)?;
+	let Some(target) = target.iter().map(|&v| ThreeBit::from_value(v)).collect::<Option<Vec<_>>>() else {
+		return Err(QuineError::NoSolution);
+	};
+	
+	find_input_for_three_bit(&program, &target)
+}
+
+pub fn find_quine(program: &[u8]) -> Result<u64, QuineError> {
+	find_input_for(program, program)
+}
+
+fn find_input_for_three_bit(program: &[ThreeBit], target: &[ThreeBit]) -> Result<u64, QuineError> {
+	// Iteration 'i' runs with A shifted right by 'i * shift', and the last one must leave A at zero, so A is built up from
+	// its most significant 'shift' bits (which produce the last output) downwards. Trying the smallest bits first at each
+	// level means the first complete match is the smallest.
+	fn search(body: &[ThreeBit], shift: u32, remaining: &[ThreeBit], a_after: u64) -> Option<u64> {
+		let Some((&want, before)) = remaining.split_last() else {
+			return Some(a_after);
+		};
+		
+		let high = a_after.checked_mul(1 << shift)?;
+		for low in 0..1u64 << shift {
+			let a = high | low;
+			// A can only be zero on the final iteration if it's also the first, or the loop would've stopped sooner.
+			if a == 0 && !before.is_empty() {
+				continue;
+			}
+			if iteration_output(body, a) == want {
+				if let Some(result) = search(body, shift, before, a) {
+					return Some(result);
+				}
+			}
+		}
+		
+		None
+	}
+	
+	let LoopShape { shift } = analyse_three_bit(program)?;
+	if target.is_empty() {
+		return Err(QuineError::NoSolution); // Every run outputs at least once.
+	}
+	if (target.len() - 1) as u64 * shift as u64 >= u64::BITS as u64 {
+		return Err(QuineError::TooLong);
+	}
+	
+	search(&program[..program.len() - 2], shift, target, 0).ok_or(QuineError::NoSolution)
+}
+
 #[aoc(day17, part2)]
 pub fn part2(input: &str) -> u64 {
+	let computer = Computer::parse(input).unwrap();
+	find_quine(&computer.program().collect::<Vec<_>>()).unwrap()
+}
+
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	let checker = Checker::new(17, input);
+	let program_text = check(input)?;
+	
+	let mut program = Vec::with_capacity(16);
+	program.extend(program_text.bytes().filter_map(ThreeBit::from_ascii));
+	
+	find_input_for_three_bit(&program, &program).map_err(|e| checker.error_at(program_text, match e {
+		QuineError::TooLong => ParseErrorKind::OutOfRange,
+		QuineError::NoSolution => ParseErrorKind::Unsolvable,
+		_ => ParseErrorKind::Unsupported
+	}))
+}
+
+// Only handles programs shaped like the puzzle's; see 'find_quine' for the general version.
+#[allow(unused)]
+pub fn part2_backtracking(input: &str) -> u64 {
 	let mut program = Vec::with_capacity(16);
 	program.extend(
 		input.split_once('P').unwrap().1["rogram: ".len()..]
@@ -225,7 +656,6 @@ pub fn part2(input: &str) -> u64 {
 	test_thing(&program, &program, 0).unwrap()
 }
 
-
 #[allow(unused)]
 pub fn part2_initial(input: &str) -> u64 {
 	let mut program = Vec::with_capacity(16);
@@ -284,3 +714,63 @@ pub fn part2_initial(input: &str) -> u64 {
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	fn run(registers: [u64; 3], program: &[u8]) -> String {
+		let mut computer = Computer::new(registers, program).unwrap();
+		computer.run().unwrap();
+		computer.output_string()
+	}
+	
+	#[test]
+	fn divides_by_large_powers_of_two() {
+		// 'adv 5' ('A >>= B') then 'out 4' ('A % 8').
+		assert_eq!(run([3 << 35, 35, 0], &[0, 5, 5, 4]), "3");
+		assert_eq!(run([u64::MAX, 63, 0], &[0, 5, 5, 4]), "1");
+		assert_eq!(run([u64::MAX, 64, 0], &[0, 5, 5, 4]), "0");
+		// 'bdv 6' and 'cdv 6' shift by C.
+		assert_eq!(run([5 << 40, 0, 40], &[6, 6, 5, 5]), "5");
+		assert_eq!(run([5 << 40, 0, 40], &[7, 6, 5, 6]), "5");
+	}
+	
+	fn assert_quine(program: &[u8], a: u64) {
+		let mut computer = Computer::new([a, 0, 0], program).unwrap();
+		computer.run().unwrap();
+		assert!(computer.output().eq(program.iter().copied()), "A = {a} gives {}", computer.output_string());
+	}
+	
+	#[test]
+	fn example_quine() {
+		let program = [0, 3, 5, 4, 3, 0];
+		assert_eq!(analyse(&program), Ok(LoopShape { shift: 3 }));
+		assert_eq!(find_quine(&program), Ok(117440));
+		assert_quine(&program, 117440);
+		assert_eq!(try_part2("Register A: 2024\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,4,3,0\n"), Ok(117440));
+	}
+	
+	#[test]
+	fn quine_using_b_and_c() {
+		let program = [2, 4, 1, 1, 7, 5, 1, 5, 4, 0, 5, 5, 0, 3, 3, 0];
+		assert_eq!(analyse(&program), Ok(LoopShape { shift: 3 }));
+		let a = find_quine(&program).unwrap();
+		assert_quine(&program, a);
+		assert_eq!(find_input_for(&[0, 3, 5, 4, 3, 0], &[1]), Err(QuineError::NoSolution));
+	}
+	
+	#[test]
+	fn rejects_other_shapes() {
+		assert_eq!(analyse(&[0, 3, 5, 4]), Err(QuineError::NotSingleLoop));
+		assert_eq!(analyse(&[0, 3, 5, 4, 3, 0, 3, 0]), Err(QuineError::NotSingleLoop));
+		assert_eq!(analyse(&[0, 3, 5, 4, 5, 4, 3, 0]), Err(QuineError::OutputsPerIteration(2)));
+		assert_eq!(analyse(&[0, 3, 0, 1, 5, 4, 3, 0]), Err(QuineError::ShiftsPerIteration(2)));
+		assert_eq!(analyse(&[0, 5, 5, 4, 3, 0]), Err(QuineError::CarriedRegister{ instruction_pointer: 0, register: 'B' }));
+		assert_eq!(analyse(&[0, 4, 5, 4, 3, 0]), Err(QuineError::ShiftNotLiteral{ instruction_pointer: 0 }));
+		assert_eq!(analyse(&[1, 1, 5, 5, 0, 3, 3, 0]), Err(QuineError::CarriedRegister{ instruction_pointer: 0, register: 'B' }));
+		
+		let error = try_part2("Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,4\n").unwrap_err();
+		assert_eq!(error.kind, ParseErrorKind::Unsupported);
+	}
+}
@@ -1,7 +1,9 @@
 use std::{collections::VecDeque, iter, mem};
 
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
 const BOUND: u8 = 70;
-const DIM: usize = BOUND as usize + 1;
+const FALLEN: usize = 1024;
 
 const START: (u8, u8) = (0, 0);
 
@@ -15,7 +17,11 @@ fn parse_coord_from_ascii(bytes: &[u8]) -> Option<(u8, u8)> {
 		&[a1, a2, b',',     b ] => (v(a1)? * 10 + v(a2)?,               v(b)? ),
 		&[    a , b',', b1, b2] => (              v(a)? , v(b1)? * 10 + v(b2)?),
 		&[a1, a2, b',', b1, b2] => (v(a1)? * 10 + v(a2)?, v(b1)? * 10 + v(b2)?),
-		_ => return None
+		_ => { // Only reachable with bounds above 99.
+			let n = |digits: &[u8]| digits.iter().try_fold(0u8, |acc, &b| acc.checked_mul(10)?.checked_add(v(b)?)).filter(|_| !digits.is_empty());
+			let comma = bytes.iter().position(|&b| b == b',')?;
+			(n(&bytes[..comma])?, n(&bytes[comma + 1..])?)
+		}
 	})
 }
 
@@ -62,17 +68,105 @@ unsafe fn do_thing(impassable: &mut [bool], bound: u8, buffer: &mut VecDeque<((u
 	None
 }
 
+// The bytes that fall into a memory space with coordinates in '0..=bound', in the order they fall.
+#[derive(Clone)]
+pub struct MemorySpace {
+	bound: u8,
+	bytes: Vec<(u8, u8)>
+}
+
+impl MemorySpace {
+	pub fn parse(input: &str, bound: u8) -> Option<Self> {
+		let mut bytes = Vec::with_capacity(4096);
+		for line in input.lines() {
+			bytes.push(parse_coord_from_ascii(line.as_bytes()).filter(|&(r, c)| r <= bound && c <= bound)?);
+		}
+		
+		Some(Self { bound, bytes })
+	}
+	
+	pub fn try_parse(input: &str, bound: u8) -> Result<Self, ParseError> {
+		check(input, bound)?;
+		Ok(unsafe { Self::parse(input, bound).unwrap_unchecked() })
+	}
+	
+	pub fn bound(&self) -> u8 {
+		self.bound
+	}
+	
+	pub fn byte_count(&self) -> usize {
+		self.bytes.len()
+	}
+	
+	fn dim(&self) -> usize {
+		self.bound as usize + 1
+	}
+	
+	// Length of the shortest path from the top-left to the bottom-right corner once the first 'fallen' bytes have landed.
+	pub fn shortest_path(&self, fallen: usize) -> Option<u32> {
+		let mut map = vec![false; self.dim() * self.dim()];
+		for &pos in self.bytes.iter().take(fallen) {
+			map[index_of(pos, self.bound)] = true;
+		}
+		if map[index_of(START, self.bound)] {
+			return None; // A byte on the start leaves nowhere to begin from.
+		}
+		
+		unsafe { do_thing(&mut map, self.bound, &mut VecDeque::with_capacity(128)) }
+	}
+	
+	// Index of the first byte after which there's no path to the exit.
+	pub fn first_blocking(&self) -> Option<usize> {
+		let mut map = vec![EMPTY; self.dim() * self.dim()];
+		// A position can be listed more than once, but only its first fall blocks anything.
+		let mut first_fall = vec![usize::MAX; self.dim() * self.dim()];
+		for (i, &pos) in enumerate(&self.bytes) {
+			map[index_of(pos, self.bound)] = WALL;
+			first_fall[index_of(pos, self.bound)] = first_fall[index_of(pos, self.bound)].min(i);
+		}
+		
+		let mut queue = VecDeque::with_capacity(8);
+		if unsafe { initial_fill(&mut map, self.bound, &mut queue) } {
+			return None; // Still reachable after every byte has fallen.
+		}
+		for (i, &pos) in enumerate(&self.bytes).rev() {
+			if first_fall[index_of(pos, self.bound)] == i && unsafe { remove_wall(&mut map, self.bound, pos, &mut queue) } {
+				return Some(i);
+			}
+		}
+		
+		None
+	}
+}
+
 #[aoc(day18, part1)]
 pub fn part1(input: &str) -> u32 {
-	let mut map = [[false; DIM]; DIM];
-	
-	for line in input.lines().take(1024) {
-		let (r, c) = unsafe { parse_coord_from_ascii(line.as_bytes()).filter(|&(r, c)| r <= BOUND && c <= BOUND).unwrap_unchecked() };
-		map[r as usize][c as usize] = true;
-		// eprintln!("[i] Parsed: {r},{c}");
+	let space = unsafe { MemorySpace::parse(input, BOUND).unwrap_unchecked() };
+	unsafe { space.shortest_path(FALLEN).unwrap_unchecked() }
+}
+
+fn check(input: &str, bound: u8) -> Result<(), ParseError> {
+	let checker = Checker::new(18, input);
+	for line in input.lines() {
+		let (r, c) = checker.split_once(line, ",")?;
+		for value in [r, c] {
+			checker.bytes_in(value, |b| b.is_ascii_digit())?;
+			// Only digits get this far, so an invalid number is one too big for a 'u8'.
+			let number = checker.number::<u8>(value).map_err(|e| match e.kind {
+				ParseErrorKind::InvalidNumber => ParseError { kind: ParseErrorKind::OutOfRange, ..e },
+				_ => e
+			})?;
+			if number > bound {
+				return Err(checker.error_at(value, ParseErrorKind::OutOfRange));
+			}
+		}
 	}
 	
-	unsafe { do_thing(map.as_flattened_mut(), BOUND, &mut VecDeque::with_capacity(128)).unwrap_unchecked() }
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	MemorySpace::try_parse(input, BOUND)?.shortest_path(FALLEN).ok_or_else(|| Checker::new(18, input).error_at_end(input, ParseErrorKind::Unsolvable))
 }
 
 const WALL: u8 = u8::MAX;
@@ -143,26 +237,22 @@ pub fn part2_outer(input: &str) -> String {
 }
 
 pub fn part2(input: &str) -> &str {
-	let mut map = [[EMPTY; DIM]; DIM];
-	
-	for line in input.lines() {
-		let (r, c) = unsafe { parse_coord_from_ascii(line.as_bytes()).filter(|&(r, c)| r <= BOUND && c <= BOUND).unwrap_unchecked() };
-		map[r as usize][c as usize] = WALL;
-		// map.as_flattened_mut()[index_of((r, c), BOUND)] = WALL;
+	let space = unsafe { MemorySpace::parse(input, BOUND).unwrap_unchecked() };
+	match space.first_blocking() {
+		Some(i) => unsafe { input.lines().nth(i).unwrap_unchecked() },
+		None => unreachable!()
 	}
-	
-	let mut queue = VecDeque::with_capacity(8);
-	unsafe {
-		initial_fill(map.as_flattened_mut(), BOUND, &mut queue);
-	}
-	for line in input.lines().rev() {
-		let point = unsafe { parse_coord_from_ascii(line.as_bytes()).filter(|&(r, c)| r <= BOUND && c <= BOUND).unwrap_unchecked() };
-		if unsafe { remove_wall(map.as_flattened_mut(), BOUND, point, &mut queue) } {
-			return line;
-		}
+}
+
+pub fn try_part2(input: &str) -> Result<&str, ParseError> {
+	match MemorySpace::try_parse(input, BOUND)?.first_blocking() {
+		Some(i) => Ok(unsafe { input.lines().nth(i).unwrap_unchecked() }),
+		None => Err(Checker::new(18, input).error_at_end(input, ParseErrorKind::Unsolvable))
 	}
-	
-	unreachable!();
+}
+
+fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
+	i.into_iter().enumerate()
 }
 
 fn filter_map<I: IntoIterator, O, F: FnMut(I::Item) -> Option<O>>(i: I, f: F) -> iter::FilterMap<I::IntoIter, F> {
@@ -172,3 +262,34 @@ fn filter_map<I: IntoIterator, O, F: FnMut(I::Item) -> Option<O>>(i: I, f: F) ->
 //	fn reverse<I: IntoIterator>(i: I) -> iter::Rev<I::IntoIter> where I::IntoIter: DoubleEndedIterator {
 //		i.into_iter().rev()
 //	}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "5,4\n4,2\n4,5\n3,0\n2,1\n6,3\n2,4\n1,5\n0,6\n3,3\n2,6\n5,1\n1,2\n5,5\n2,5\n6,5\n1,4\n0,4\n6,4\n1,1\n6,1\n1,0\n0,5\n1,6\n2,0\n";
+	
+	#[test]
+	fn example() {
+		let space = MemorySpace::parse(EXAMPLE, 6).unwrap();
+		assert_eq!(space.shortest_path(12), Some(22));
+		assert_eq!(space.first_blocking(), Some(20));
+	}
+	
+	#[test]
+	fn byte_on_the_start() {
+		let space = MemorySpace::parse("0,0\n", 2).unwrap();
+		assert_eq!((space.shortest_path(0), space.shortest_path(1)), (Some(4), None));
+		assert_eq!(space.first_blocking(), Some(0));
+		assert_eq!(MemorySpace::try_parse("0,0\n", 2).unwrap().shortest_path(1), None);
+	}
+	
+	#[test]
+	fn check_errors() {
+		let kind = |input| check(input, 6).unwrap_err().kind;
+		assert_eq!(kind("3,\n"), ParseErrorKind::UnexpectedEnd);
+		assert_eq!(kind("3,7\n"), ParseErrorKind::OutOfRange);
+		assert_eq!(kind("3,256\n"), ParseErrorKind::OutOfRange);
+		assert_eq!(kind("3,x\n"), ParseErrorKind::UnexpectedByte(b'x'));
+	}
+}
@@ -1,4 +1,147 @@
-use std::{collections::BinaryHeap, iter};
+use std::{collections::BinaryHeap, iter, mem};
+
+use crate::error::{Checker, ParseError};
+
+#[derive(Clone, Default)]
+struct Node {
+	children: Vec<(u8, u32)>,
+	// Whether a pattern ends here.
+	terminal: bool
+}
+
+// A trie of towel patterns. Node 0 is the root (the empty prefix).
+#[derive(Clone)]
+pub struct Patterns {
+	nodes: Vec<Node>,
+	count: usize
+}
+
+impl Default for Patterns {
+	fn default() -> Self {
+		Self { nodes: vec![Node::default()], count: 0 }
+	}
+}
+
+impl Patterns {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	
+	// From the puzzle's ", "-separated list.
+	pub fn parse(line: &str) -> Self {
+		let mut patterns = Self::new();
+		for pattern in line.split(", ") {
+			patterns.insert(pattern);
+		}
+		patterns
+	}
+	
+	// Empty patterns are ignored, since they'd make for infinitely many arrangements.
+	pub fn insert(&mut self, pattern: &str) {
+		if pattern.is_empty() {
+			return;
+		}
+		
+		let mut node = 0;
+		for b in pattern.bytes() {
+			node = match self.child(node, b) {
+				Some(child) => child,
+				None => {
+					let child = self.nodes.len();
+					self.nodes[node].children.push((b, child as u32));
+					self.nodes.push(Node::default());
+					child
+				}
+			};
+		}
+		
+		let added = !mem::replace(&mut self.nodes[node].terminal, true);
+		self.count += added as usize;
+	}
+	
+	pub fn len(&self) -> usize {
+		self.count
+	}
+	
+	pub fn is_empty(&self) -> bool {
+		self.count == 0
+	}
+	
+	fn child(&self, node: usize, b: u8) -> Option<usize> {
+		self.nodes[node].children.iter().find_map(|&(c, child)| (c == b).then_some(child as usize))
+	}
+	
+	// The length of every pattern that 'design' starts with, shortest first.
+	fn prefixes_of<'a>(&'a self, design: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+		let mut node = 0;
+		let path = design.iter().map_while(move |&b| {
+			node = self.child(node, b)?;
+			Some(node)
+		});
+		
+		enumerate(path).filter_map(|(i, node)| self.nodes[node].terminal.then_some(i + 1))
+	}
+	
+	// An empty design isn't a towel, so there's no way to make one.
+	pub fn can_arrange(&self, design: &str) -> bool {
+		!design.is_empty() && self.finishable(design.as_bytes())[0]
+	}
+	
+	// Whether the rest of 'design' from each position can be made from patterns.
+	fn finishable(&self, design: &[u8]) -> Vec<bool> {
+		let mut finishable = vec![false; design.len() + 1];
+		finishable[design.len()] = true;
+		for start in (0..design.len()).rev() {
+			finishable[start] = self.prefixes_of(&design[start..]).any(|length| finishable[start + length]);
+		}
+		finishable
+	}
+	
+	pub fn arrangement_count(&self, design: &str) -> u128 {
+		if design.is_empty() {
+			return 0;
+		}
+		
+		let design = design.as_bytes();
+		let mut ways = vec![0u128; design.len() + 1];
+		ways[0] = 1;
+		
+		for start in 0..design.len() {
+			let ways_here = ways[start];
+			if ways_here > 0 {
+				for length in self.prefixes_of(&design[start..]) {
+					ways[start + length] += ways_here;
+				}
+			}
+		}
+		
+		ways[design.len()]
+	}
+	
+	// Every way to make 'design', as the patterns used in order. There can be a huge number of these, so this is only meant for small designs.
+	pub fn arrangements<'d>(&self, design: &'d str) -> Vec<Vec<&'d str>> {
+		let finishable = self.finishable(design.as_bytes());
+		let mut arrangements = Vec::new();
+		if !design.is_empty() && finishable[0] {
+			self.collect_arrangements(design, 0, &finishable, &mut Vec::new(), &mut arrangements);
+		}
+		arrangements
+	}
+	
+	fn collect_arrangements<'d>(&self, design: &'d str, start: usize, finishable: &[bool], pieces: &mut Vec<&'d str>, arrangements: &mut Vec<Vec<&'d str>>) {
+		if start == design.len() {
+			arrangements.push(pieces.clone());
+			return;
+		}
+		
+		// Patterns and designs are both valid UTF-8, so a match starting on a character boundary also ends on one.
+		for length in self.prefixes_of(&design.as_bytes()[start..]).filter(|&length| finishable[start + length]) {
+			pieces.push(&design[start..start + length]);
+			self.collect_arrangements(design, start + length, finishable, pieces, arrangements);
+			pieces.pop();
+		}
+	}
+}
 
 fn can_fill_towel(target: &str, components_by_length: &[&str], buffer: &mut BinaryHeap<usize>, visited_buffer: &mut Vec<bool>) -> bool {
 	let Some(new_size) = target.len().checked_sub(1) else {
@@ -38,6 +181,13 @@ fn can_fill_towel(target: &str, components_by_length: &[&str], buffer: &mut Bina
 #[aoc(day19, part1)]
 pub fn part1(input: &str) -> usize {
 	let mut lines = input.lines();
+	let patterns = Patterns::parse(lines.next().unwrap_or_default());
+	lines.skip(1).filter(|design| patterns.can_arrange(design)).count()
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> usize {
+	let mut lines = input.lines();
 	
 	let mut patterns = Vec::with_capacity(500);
 	patterns.extend(lines.next().unwrap_or_default().split(", ").filter(|&s| !s.is_empty()));
@@ -52,6 +202,30 @@ pub fn part1(input: &str) -> usize {
 	lines.filter(|target| can_fill_towel(target, &patterns, &mut queue, &mut visited)).count()
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(19, input);
+	let mut lines = input.lines();
+	
+	// Both parts slice designs with 'get_unchecked', which relies on every byte being its own character.
+	for pattern in lines.next().unwrap_or_default().split(", ") {
+		checker.bytes_in(pattern, |b| b.is_ascii_alphabetic())?;
+	}
+	
+	if let Some(separator) = lines.next() {
+		checker.end(separator)?;
+	}
+	
+	for design in lines {
+		checker.bytes_in(design, |b| b.is_ascii_alphabetic())?;
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 fn ways_to_fill(target: &str, components_by_length: &[&str], buffer: &mut Vec<u64>) -> u64 {
 	debug_assert!(!components_by_length.iter().copied().any(str::is_empty));
 	let Some(new_size) = target.len().checked_sub(1) else {
@@ -89,7 +263,14 @@ fn ways_to_fill(target: &str, components_by_length: &[&str], buffer: &mut Vec<u6
 }
 
 #[aoc(day19, part2)]
-pub fn part2(input: &str) -> u64 {
+pub fn part2(input: &str) -> u128 {
+	let mut lines = input.lines();
+	let patterns = Patterns::parse(lines.next().unwrap_or_default());
+	lines.skip(1).map(|design| patterns.arrangement_count(design)).sum()
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u64 {
 	let mut lines = input.lines();
 	
 	let mut patterns = Vec::with_capacity(500);
@@ -99,10 +280,18 @@ pub fn part2(input: &str) -> u64 {
 	lines.next();
 	
 	let mut storage = Vec::with_capacity(128);
-
+	
 	lines.map(|target| ways_to_fill(&target, &patterns, &mut storage)).sum()
 }
 
+pub fn try_part2(input: &str) -> Result<u128, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
+fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
+	i.into_iter().enumerate()
+}
+
 fn reverse<I: IntoIterator>(i: I) -> iter::Rev<I::IntoIter> where I::IntoIter: DoubleEndedIterator {
 	i.into_iter().rev()
 }
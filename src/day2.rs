@@ -1,5 +1,7 @@
-#[derive(Copy, Eq, Clone, PartialEq)]
-enum Dir {
+use crate::error::{Checker, ParseError};
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum Dir {
 	Increasing,
 	Decreasing
 }
@@ -109,8 +111,115 @@ impl StateTracker {
 	}
 }
 
+// How far apart neighbouring levels are allowed to be, and how many levels the Problem Dampener can remove.
+// Levels always have to strictly increase or decrease, so a 'min_step' of 0 is the same as 1.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Tolerance {
+	pub min_step: u32,
+	pub max_step: u32,
+	pub removals: usize
+}
+
+impl Tolerance {
+	pub const PART1: Self = Self { min_step: 1, max_step: 3, removals: 0 };
+	pub const PART2: Self = Self { min_step: 1, max_step: 3, removals: 1 };
+	
+	fn allows(self, a: i32, b: i32, direction: Dir) -> bool {
+		Dir::compare(&a, &b) == Some(direction) && (self.min_step..=self.max_step).contains(&a.abs_diff(b))
+	}
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Analysis {
+	// Which way the remaining levels go, if the report is safe and at least two are left.
+	pub direction: Option<Dir>,
+	// The first level that breaks the rules in the report as given, going by the direction of the first two levels.
+	pub first_violation: Option<usize>,
+	// The levels the Problem Dampener removes (empty if the report is already safe), or 'None' if the report can't be made safe.
+	pub removed: Option<Vec<usize>>
+}
+
+impl Analysis {
+	pub fn is_safe(&self) -> bool {
+		self.removed.is_some()
+	}
+}
+
+pub fn parse_report(line: &str) -> Option<Vec<i32>> {
+	line.split_whitespace().map(|num| num.parse().ok()).collect()
+}
+
+pub fn parse_reports(input: &str) -> Option<Vec<Vec<i32>>> {
+	input.lines().map(parse_report).collect()
+}
+
+fn first_violation(levels: &[i32], tolerance: Tolerance) -> Option<usize> {
+	let direction = Dir::compare(levels.first()?, levels.get(1)?).unwrap_or(Dir::Increasing);
+	(1..levels.len()).find(|&i| !tolerance.allows(levels[i - 1], levels[i], direction))
+}
+
+// The fewest levels to remove for 'levels' to go in 'direction', preferring to remove earlier levels when there's a choice.
+fn fewest_removals(levels: &[i32], direction: Dir, tolerance: Tolerance) -> (Vec<usize>, usize) {
+	// How many levels have to be removed after each level if it's kept, and which level is kept next ('len' for none).
+	let len = levels.len();
+	let mut best = vec![(0, len); len];
+	for i in (0..len).rev() {
+		best[i] = (len - 1 - i, len);
+		for j in (i + 1..len).filter(|&j| tolerance.allows(levels[i], levels[j], direction)) {
+			let removals = j - i - 1 + best[j].0;
+			// Skipping over more levels when it's no worse means removing earlier ones.
+			if removals < best[i].0 || (removals == best[i].0 && j > best[i].1) {
+				best[i] = (removals, j);
+			}
+		}
+	}
+	
+	let Some(mut kept) = (0..len).rev().min_by_key(|&i| i + best[i].0) else {
+		return (Vec::new(), 0);
+	};
+	
+	let mut removed: Vec<usize> = (0..kept).collect();
+	let mut kept_count = 1;
+	while kept < len {
+		let next = best[kept].1;
+		removed.extend(kept + 1..next);
+		kept_count += (next < len) as usize;
+		kept = next;
+	}
+	
+	(removed, kept_count)
+}
+
+pub fn analyse(levels: &[i32], tolerance: Tolerance) -> Analysis {
+	let first_violation = first_violation(levels, tolerance);
+	
+	let options = [Dir::Increasing, Dir::Decreasing].map(|direction| (direction, fewest_removals(levels, direction, tolerance)));
+	// Fewer removals wins, then removing earlier levels, then going up.
+	let (direction, (removed, kept)) = unsafe {
+		options.into_iter().min_by(|(_, (a, _)), (_, (b, _))| (a.len(), a).cmp(&(b.len(), b))).unwrap_unchecked()
+	};
+	
+	if first_violation.is_none() {
+		Analysis { direction: (levels.len() > 1).then_some(direction), first_violation, removed: Some(Vec::new()) }
+	} else if removed.len() <= tolerance.removals {
+		Analysis { direction: (kept > 1).then_some(direction), first_violation, removed: Some(removed) }
+	} else {
+		Analysis { direction: None, first_violation, removed: None }
+	}
+}
+
+pub fn count_safe(reports: &[Vec<i32>], tolerance: Tolerance) -> usize {
+	reports.iter().filter(|levels| analyse(levels, tolerance).is_safe()).count()
+}
+
 #[aoc(day2, part2)]
 pub fn part2(input: &str) -> usize {
+	let reports = unsafe { parse_reports(input).unwrap_unchecked() };
+	count_safe(&reports, Tolerance::PART2)
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> usize {
 	input.lines().filter(|line| {
 		let mut ascending_state = StateTracker::for_direction(Dir::Increasing);
 		let mut descending_state = StateTracker::for_direction(Dir::Decreasing);
@@ -129,8 +238,29 @@ pub fn part2(input: &str) -> usize {
 	}).count()
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(2, input);
+	for line in input.lines() {
+		for num in line.split(' ') {
+			checker.number::<i32>(num)?;
+		}
+	}
+	
+	Ok(())
+}
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 #[aoc(day2, part1)]
 pub fn part1(input: &str) -> usize {
+	let reports = unsafe { parse_reports(input).unwrap_unchecked() };
+	count_safe(&reports, Tolerance::PART1)
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> usize {
 	input.lines().filter(|line| {
 		let mut direction = None;
 		let mut previous = None;
@@ -156,4 +286,8 @@ pub fn part1(input: &str) -> usize {
 		
 		true // previous.is_some() // lines should probably have at least one reading to count
 	}).count()
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+	check(input).map(|()| part1(input))
 }
\ No newline at end of file
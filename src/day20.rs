@@ -1,123 +1,7 @@
-use std::{iter, marker::PhantomData, num::NonZero, ptr::NonNull, slice};
+use std::iter;
 
-#[derive(Clone)]
-struct Grid<T> {
-	grid: Vec<T>,
-	row_length: NonZero<usize>
-}
-
-impl<T> Grid<T> {
-	#[allow(unused)]
-	pub fn from_vec(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
-		assert_eq!(grid.len() % row_length, 0);
-		assert!( !grid.is_empty() );
-		
-		unsafe { Self::from_vec_unchecked(grid, row_length) }
-	}
-	
-	#[allow(unused)]
-	pub fn from_first_row(grid: Vec<T>) -> Result<Self, Vec<T>> {
-		if let Some(row_length) = NonZero::new(grid.len()) {
-			Ok(unsafe { Self::from_vec_unchecked(grid, row_length) })
-		} else {
-			Err(grid)
-		}
-	}
-	
-	pub unsafe fn from_vec_unchecked(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
-		Self { grid, row_length }
-	}
-	
-	pub unsafe fn as_vec_mut(&mut self) -> &mut Vec<T> {
-		&mut self.grid
-	}
-	
-	#[allow(unused)]
-	pub fn reserve_rows(&mut self, rows: usize) {
-		let capacity = self.row_length().get() * rows;
-		unsafe {
-			self.as_vec_mut().reserve(capacity);
-		}
-	}
-	
-	#[allow(unused)]
-	pub fn add_filled_row(&mut self, value: T) -> &mut [T] where T: Clone {
-		let prev_len = self.total_length().get();
-		let row_len = self.row_length().get();
-		unsafe {
-			self.as_vec_mut().resize(prev_len + row_len, value);
-			self.as_slice_mut().get_unchecked_mut(prev_len..)
-		}
-	}
-	
-	
-	pub fn as_slice(&self) -> &[T] {
-		&self.grid
-	}
-	
-	pub fn as_slice_mut(&mut self) -> &mut [T] {
-		&mut self.grid
-	}
-	
-	
-	pub fn get(&self, row: usize, col: usize) -> Option<&T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
-	}
-	
-	pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_slice_mut().get_unchecked_mut(i) })
-	}
-	
-	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &T {
-		self.as_slice().get_unchecked(self.to_index_unchecked(row, col))
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked_mut(&mut self, row: usize, col: usize) -> &mut T {
-		let index = self.to_index_unchecked(row, col);
-		self.as_slice_mut().get_unchecked_mut(index)
-	}
-	
-	
-	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
-		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
-	}
-	
-	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
-		row * self.row_length().get() + col
-	}
-	
-	#[allow(unused)]
-	pub fn to_row_col(&self, index: usize) -> Option<(usize, usize)> {
-		(index < self.total_length().get()).then(|| self.to_row_col_unchecked(index))
-	}
-	
-	#[allow(unused)]
-	pub fn to_row_col_unchecked(&self, index: usize) -> (usize, usize) {
-		(index / self.row_length(), index % self.row_length())
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get_row(&self, row: usize) -> Option<&[T]> {
-		let row_start = row.checked_mul(self.row_length().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_slice().get(row_start..row_end)
-	}
-	
-	
-	pub fn row_count(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.total_length().get() / self.row_length()) }
-	}
-	
-	pub fn row_length(&self) -> NonZero<usize> {
-		self.row_length
-	}
-	
-	pub fn total_length(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.as_slice().len()) }
-	}
-}
+use crate::error::{Checker, ParseError, ParseErrorKind};
+use crate::grid::{Grid, GridRefWithLn};
 
 const WALL: u32 = 0;
 const EMPTY: u32 = u32::MAX;
@@ -158,7 +42,7 @@ fn count_cheats(grid: &mut Grid<u32>, goal: (usize, usize), minimum_skip: u32) -
 
 #[aoc(day20, part1)]
 pub fn part1(input: &str) -> u32 {
-	let data = unsafe { as_byte_grid(input).unwrap_unchecked() };
+	let data = unsafe { GridRefWithLn::from_text(input).unwrap_unchecked() };
 	let (width, height, width_ln) = (data.row_length().get(), data.row_count().get(), data.row_length_with_ln().get());
 	
 	let mut grid = vec![WALL; width * height];
@@ -177,116 +61,26 @@ pub fn part1(input: &str) -> u32 {
 	unsafe { count_cheats(&mut Grid::from_vec_unchecked(grid, data.row_length()), end.unwrap_unchecked(), 100).unwrap_unchecked() }
 }
 
-struct GridRefWithLn<'a, T> {
-	grid_ptr: NonNull<T>,
-	total_length_with_ln: NonZero<usize>,
-	row_length_with_ln: NonZero<usize>,
-	marker: PhantomData<&'a [T]>
-}
-
-impl<'a, T> Copy for GridRefWithLn<'a, T> {}
-
-impl<'a, T> Clone for GridRefWithLn<'a, T> {
-	fn clone(&self) -> Self {
-		*self
-	}
-}
-
-impl<'a, T> GridRefWithLn<'a, T> {
-	pub fn from_slice(grid: &'a [T], row_length: NonZero<usize>) -> Option<Self> {
-		let row_length_with_ln = row_length.checked_add(1)?;
-		let total_length_with_ln = NonZero::new(
-			if grid.len() % row_length_with_ln == 0 {
-				grid.len()
-			} else {
-				grid.len() + 1
-			}
-		)?;
-		
-		(total_length_with_ln.get() % row_length_with_ln == 0).then(||
-			Self {
-				grid_ptr: unsafe { NonNull::new_unchecked(grid.as_ptr() as *mut _) },
-				total_length_with_ln,
-				row_length_with_ln,
-				marker: PhantomData
-			}
-		)
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn from_raw_parts(grid_ptr: NonNull<T>, total_length_with_ln: NonZero<usize>, row_length_with_ln: NonZero<usize>) -> Self {
-		Self { grid_ptr, total_length_with_ln, row_length_with_ln, marker: PhantomData }
-	}
-	
-	
-	pub fn as_slice(&self) -> &'a [T] { // Does not include the final possibly-uninitialized '\n'
-		unsafe { slice::from_raw_parts(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
-	}
-	
-	#[allow(unused)]
-	pub fn as_ptr(&self) -> NonNull<T> {
-		self.grid_ptr
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get(&self, row: usize, col: usize) -> Option<&'a T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
-	}
-	
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &'a T {
-		unsafe { self.as_slice().get_unchecked(self.to_index_unchecked(row, col)) }
-	}
-	
-	
-	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
-		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
-	}
-	
-	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
-		row * self.row_length_with_ln().get() + col
-	}
-	
-	#[allow(unused)]
-	pub fn to_row_col(&self, index: usize) -> Option<(usize, usize)> {
-		(index < self.total_length_with_ln().get()).then(|| self.to_row_col_unchecked(index)).filter(|&(_, c)| c < self.row_length().get())
-	}
-	
-	pub fn to_row_col_unchecked(&self, index: usize) -> (usize, usize) {
-		(index / self.row_length_with_ln(), index % self.row_length_with_ln())
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get_row(&self, row: usize) -> Option<&[T]> {
-		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_slice().get(row_start..row_end)
-	}
-	
-	
-	pub fn row_count(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.total_length_with_ln().get() / self.row_length_with_ln()) }
-	}
-	
-	pub fn row_length(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.row_length_with_ln().get() - 1) }
-	}
-	
-	pub fn row_length_with_ln(&self) -> NonZero<usize> {
-		self.row_length_with_ln
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(20, input);
+	let grid = checker.grid(input, |b| matches!(b, b'#' | b'.' | b'S' | b'E'))?;
+	checker.unique(input, b'S')?;
+	let end = checker.unique(input, b'E')?;
+	
+	// Both parts walk the track backwards from the end and expect it to never branch.
+	for (i, _) in enumerate(grid.as_slice()).filter(|&(_, &b)| !matches!(b, b'#' | b'\n')) {
+		let (row, col) = grid.to_row_col_unchecked(i);
+		let open = grid.neighbors(row, col).filter(|&(r, c)| grid.get(r, c).is_some_and(|&b| b != b'#')).count();
+		if open > if i == end { 1 } else { 2 } {
+			return Err(checker.error_at_offset(i, ParseErrorKind::Unsupported));
+		}
 	}
 	
-	pub fn total_length_with_ln(&self) -> NonZero<usize> {
-		self.total_length_with_ln // unsafe { NonZero::new_unchecked(self.slice().len()) }
-	}
+	Ok(())
 }
 
-fn as_byte_grid(input: &str) -> Option<GridRefWithLn<u8>> {
-	let row_length = input.find('\n').unwrap_or(input.len());
-	NonZero::new(row_length).and_then(|row_length| GridRefWithLn::from_slice(input.as_bytes(), row_length))
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	check(input).map(|()| part1(input))
 }
 
 fn count_cheats_dx<T, F: FnMut(&T) -> bool>(grid: GridRefWithLn<T>, goal: (usize, usize), minimum_skip: usize, cheat_duration: usize,
@@ -330,13 +124,17 @@ fn count_cheats_dx<T, F: FnMut(&T) -> bool>(grid: GridRefWithLn<T>, goal: (usize
 
 #[aoc(day20, part2)]
 pub fn part2(input: &str) -> usize {
-	let grid = unsafe { as_byte_grid(input).unwrap_unchecked() };
+	let grid = unsafe { GridRefWithLn::from_text(input).unwrap_unchecked() };
 	
 	let end = unsafe { input.find('E').map(|i| (i / grid.row_length_with_ln(), i % grid.row_length_with_ln())).unwrap_unchecked() };
 	
 	unsafe { count_cheats_dx(grid, end, 100, 20, &mut Vec::with_capacity(10_000), |&b| b != b'#').unwrap_unchecked() }
 }
 
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
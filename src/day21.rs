@@ -1,5 +1,7 @@
 use std::{cmp::{Ordering, Reverse}, collections::BinaryHeap, fmt, iter, ops};
 
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum Numpad {
 	B7 = 7, B8 = 8, B9 = 9,
@@ -79,6 +81,21 @@ pub fn part1(input: &str) -> u32 {
 	}).sum()
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(21, input);
+	for line in input.lines() {
+		let (digits, rest) = line.split_at_checked(3).ok_or_else(|| checker.error_at_end(line, ParseErrorKind::UnexpectedEnd))?;
+		checker.bytes_in(digits, |b| b.is_ascii_digit())?;
+		checker.end(checker.prefix(rest, "A")?)?;
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 const NUMPAD_NAVIGATION_COSTS_25: NumpadMap<NumpadMap<u64>> = NumpadMap([
 	     // To:      0     ,      1     ,      2     ,      3     ,      4     ,      5     ,      6     ,      7     ,      8     ,      9     ,      A     
 	NumpadMap([           1, 31420065369, 14752615084, 24095973437, 31420065370, 14752615085, 24095973438, 31420065371, 14752615086, 24095973439, 14287938116 ]), // From: 0
@@ -105,6 +122,10 @@ pub fn part2(input: &str) -> u64 {
 	}).sum()
 }
 
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn fold<I: IntoIterator, T, F: FnMut(T, I::Item) -> T>(i: I, init: T, f: F) -> T {
 	i.into_iter().fold(init, f)
 }
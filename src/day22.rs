@@ -1,5 +1,7 @@
 use rustc_hash::{FxBuildHasher, FxHashMap as HashMap, FxHashSet as HashSet};
 
+use crate::error::{Checker, ParseError};
+
 fn next(mut secret: u32) -> u32 {
 	secret = (secret ^ (secret << 6)) & 0xFFFFFF;
 	secret = (secret ^ (secret >> 5)) & 0xFFFFFF;
@@ -17,6 +19,19 @@ pub fn part1(input: &str) -> u64 {
 	}).sum()
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(22, input);
+	for line in input.lines() {
+		checker.number::<u32>(line)?;
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 #[aoc(day22, part2)]
 pub fn part2(input: &str) -> u32 {
 	let mut counts = HashMap::with_capacity_and_hasher(41000, FxBuildHasher);
@@ -47,6 +62,10 @@ pub fn part2(input: &str) -> u32 {
 	max_bananas
 }
 
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 
 #[allow(unused)]
 pub fn part2_30_minute_bruteforce(input: &str) -> u32 {
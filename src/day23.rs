@@ -1,13 +1,276 @@
 use rustc_hash::{FxBuildHasher, FxHashMap as HashMap, FxHashSet as HashSet};
 
-use std::{hash::Hash, iter};
+use std::{fmt::Write as _, hash::Hash, iter};
+
+use crate::error::{Checker, ParseError, ParseErrorKind};
 
 fn with_default_capacity<T: Hash>() -> HashSet<T> {
 	HashSet::with_capacity_and_hasher(13, FxBuildHasher)
 }
 
+// Fixed-size set of node indices, one bit each.
+#[derive(Clone, Eq, PartialEq)]
+struct BitSet(Vec<u64>);
+
+impl BitSet {
+	fn empty(len: usize) -> Self {
+		Self(vec![0; len.div_ceil(64)])
+	}
+	
+	fn full(len: usize) -> Self {
+		let mut set = Self::empty(len);
+		for i in 0..len {
+			set.insert(i);
+		}
+		set
+	}
+	
+	fn from_ids(len: usize, ids: &[usize]) -> Self {
+		let mut set = Self::empty(len);
+		for &id in ids {
+			set.insert(id);
+		}
+		set
+	}
+	
+	fn insert(&mut self, i: usize) {
+		self.0[i / 64] |= 1 << (i % 64);
+	}
+	
+	fn remove(&mut self, i: usize) {
+		self.0[i / 64] &= !(1 << (i % 64));
+	}
+	
+	fn contains(&self, i: usize) -> bool {
+		self.0[i / 64] & 1 << (i % 64) != 0
+	}
+	
+	fn len(&self) -> usize {
+		self.0.iter().map(|w| w.count_ones() as usize).sum()
+	}
+	
+	fn intersection(&self, other: &Self) -> Self {
+		Self(iter::zip(&self.0, &other.0).map(|(a, b)| a & b).collect())
+	}
+	
+	fn intersection_len(&self, other: &Self) -> usize {
+		iter::zip(&self.0, &other.0).map(|(a, b)| (a & b).count_ones() as usize).sum()
+	}
+	
+	fn difference(&self, other: &Self) -> Self {
+		Self(iter::zip(&self.0, &other.0).map(|(a, b)| a & !b).collect())
+	}
+	
+	fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+		enumerate(&self.0).flat_map(|(i, &word)| {
+			iter::successors(Some(word).filter(|&w| w != 0), |&w| Some(w & (w - 1)).filter(|&w| w != 0))
+					.map(move |w| i * 64 + w.trailing_zeros() as usize)
+		})
+	}
+}
+
+// The network map, with computers numbered in order of first appearance.
+#[derive(Clone)]
+pub struct LanGraph {
+	names: Vec<[u8; 2]>,
+	ids: HashMap<[u8; 2], usize>,
+	adjacency: Vec<BitSet>
+}
+
+impl LanGraph {
+	pub fn parse(input: &str) -> Option<Self> {
+		let mut names = Vec::with_capacity(520);
+		let mut ids = HashMap::with_capacity_and_hasher(520, FxBuildHasher);
+		let mut edges = Vec::with_capacity(input.len() / 6);
+		for line in input.lines() {
+			let &[a1, a2, b'-', b1, b2] = line.as_bytes() else { return None };
+			if [a1, a2] == [b1, b2] {
+				return None;
+			}
+			let [a, b] = [[a1, a2], [b1, b2]].map(|name| *ids.entry(name).or_insert_with(|| {
+				names.push(name);
+				names.len() - 1
+			}));
+			edges.push((a, b));
+		}
+		if !names.iter().flatten().all(u8::is_ascii) {
+			return None;
+		}
+		
+		let mut adjacency = vec![BitSet::empty(names.len()); names.len()];
+		for (a, b) in edges {
+			adjacency[a].insert(b);
+			adjacency[b].insert(a);
+		}
+		
+		Some(Self { names, ids, adjacency })
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+	
+	pub fn len(&self) -> usize {
+		self.names.len()
+	}
+	
+	pub fn is_empty(&self) -> bool {
+		self.names.is_empty()
+	}
+	
+	pub fn name(&self, id: usize) -> &str {
+		unsafe { std::str::from_utf8_unchecked(&self.names[id]) } // 'parse' only accepts ASCII.
+	}
+	
+	pub fn id(&self, name: &str) -> Option<usize> {
+		self.ids.get(name.as_bytes()).copied()
+	}
+	
+	pub fn are_connected(&self, a: usize, b: usize) -> bool {
+		self.adjacency[a].contains(b)
+	}
+	
+	pub fn neighbours(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+		self.adjacency[id].iter()
+	}
+	
+	// Sorted, comma-separated names, as used for the password.
+	pub fn names_of(&self, clique: &[usize]) -> String {
+		let mut names: Vec<&str> = clique.iter().map(|&id| self.name(id)).collect();
+		names.sort_unstable();
+		names.join(",")
+	}
+	
+	// Every clique of exactly 'size' computers (each in increasing order of id) for which 'keep' returns true.
+	pub fn cliques_of_size(&self, size: usize, mut keep: impl FnMut(&[usize]) -> bool) -> Vec<Vec<usize>> {
+		fn extend(graph: &LanGraph, size: usize, clique: &mut Vec<usize>, candidates: BitSet,
+				keep: &mut impl FnMut(&[usize]) -> bool, found: &mut Vec<Vec<usize>>) {
+			if clique.len() == size {
+				if keep(clique) {
+					found.push(clique.clone());
+				}
+				return;
+			}
+			
+			for next in candidates.iter() {
+				// Only going upwards means each clique is only found once.
+				let mut later = graph.adjacency[next].clone();
+				for below in 0..=next {
+					later.remove(below);
+				}
+				
+				clique.push(next);
+				extend(graph, size, clique, candidates.intersection(&later), keep, found);
+				clique.pop();
+			}
+		}
+		
+		let mut found = Vec::new();
+		if size > 0 {
+			extend(self, size, &mut Vec::with_capacity(size), BitSet::full(self.len()), &mut keep, &mut found);
+		}
+		found
+	}
+	
+	// Calls 'f' with every maximal clique (one that no other computer could be added to), using Bron–Kerbosch with pivoting.
+	pub fn for_each_maximal_clique(&self, mut f: impl FnMut(&[usize])) {
+		fn expand(graph: &LanGraph, clique: &mut Vec<usize>, mut candidates: BitSet, mut excluded: BitSet, f: &mut impl FnMut(&[usize])) {
+			let Some(pivot) = candidates.iter().chain(excluded.iter()).max_by_key(|&u| candidates.intersection_len(&graph.adjacency[u])) else {
+				f(clique);
+				return;
+			};
+			
+			for v in candidates.difference(&graph.adjacency[pivot]).iter() {
+				clique.push(v);
+				expand(graph, clique, candidates.intersection(&graph.adjacency[v]), excluded.intersection(&graph.adjacency[v]), f);
+				clique.pop();
+				
+				candidates.remove(v);
+				excluded.insert(v);
+			}
+		}
+		
+		if !self.is_empty() {
+			expand(self, &mut Vec::new(), BitSet::full(self.len()), BitSet::empty(self.len()), &mut f);
+		}
+	}
+	
+	pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+		let mut cliques = Vec::new();
+		self.for_each_maximal_clique(|clique| cliques.push(clique.to_vec()));
+		cliques
+	}
+	
+	// As 'for_each_maximal_clique', but skipping any branch that can't beat the best clique so far.
+	// Returned in increasing order of id; of several equally large cliques, the one whose ids come first in that order wins.
+	pub fn maximum_clique(&self) -> Vec<usize> {
+		fn expand(graph: &LanGraph, clique: &mut Vec<usize>, mut candidates: BitSet, mut excluded: BitSet, best: &mut Vec<usize>) {
+			let Some(pivot) = candidates.iter().chain(excluded.iter()).max_by_key(|&u| candidates.intersection_len(&graph.adjacency[u])) else {
+				if clique.len() >= best.len() {
+					let mut sorted = clique.clone();
+					sorted.sort_unstable();
+					if clique.len() > best.len() || sorted < *best {
+						*best = sorted;
+					}
+				}
+				return;
+			};
+			
+			for v in candidates.difference(&graph.adjacency[pivot]).iter() {
+				// Ties still need looking at, in case they come first.
+				if clique.len() + candidates.len() < best.len() {
+					return;
+				}
+				
+				clique.push(v);
+				expand(graph, clique, candidates.intersection(&graph.adjacency[v]), excluded.intersection(&graph.adjacency[v]), best);
+				clique.pop();
+				
+				candidates.remove(v);
+				excluded.insert(v);
+			}
+		}
+		
+		let mut best = Vec::new();
+		if !self.is_empty() {
+			expand(self, &mut Vec::new(), BitSet::full(self.len()), BitSet::empty(self.len()), &mut best);
+		}
+		best
+	}
+	
+	// Renders the graph for Graphviz, with the maximum clique highlighted.
+	pub fn to_dot(&self) -> String {
+		let largest = BitSet::from_ids(self.len(), &self.maximum_clique());
+		
+		let mut dot = String::with_capacity(self.len() * 64);
+		let _ = writeln!(dot, "graph lan {{");
+		let _ = writeln!(dot, "\tnode [shape=circle, style=filled, fillcolor=white];");
+		for id in largest.iter() {
+			let _ = writeln!(dot, "\t\"{}\" [fillcolor=tomato];", self.name(id).escape_default());
+		}
+		
+		for (a, connected) in enumerate(&self.adjacency) {
+			for b in connected.iter().filter(|&b| b > a) {
+				let in_largest = largest.contains(a) && largest.contains(b);
+				let _ = writeln!(dot, "\t\"{}\" -- \"{}\"{};", self.name(a).escape_default(), self.name(b).escape_default(),
+						if in_largest { " [color=red, penwidth=2]" } else { "" });
+			}
+		}
+		
+		dot.push_str("}\n");
+		dot
+	}
+}
+
 #[aoc(day23, part1)]
 pub fn part1(input: &str) -> usize {
+	let graph = unsafe { LanGraph::parse(input).unwrap_unchecked() };
+	graph.cliques_of_size(3, |clique| clique.iter().any(|&id| graph.name(id).starts_with('t'))).len()
+}
+
+#[allow(unused)]
+pub fn part1_incremental(input: &str) -> usize {
 	let mut connections = HashMap::with_capacity_and_hasher(520, FxBuildHasher);
 	let mut triplets = 0;
 	
@@ -24,8 +287,30 @@ pub fn part1(input: &str) -> usize {
 	triplets
 }
 
-#[aoc(day23, part2)]
-pub fn part2(input: &str) -> String {
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(23, input);
+	for line in input.lines() {
+		// Part two joins the names straight into a 'String', so they have to be ASCII.
+		let (a, b) = checker.split_once(line, "-")?;
+		for name in [a, b] {
+			let (name, rest) = name.split_at_checked(2).ok_or_else(|| checker.error_at_end(name, ParseErrorKind::UnexpectedEnd))?;
+			checker.bytes_in(name, |b| b.is_ascii_alphanumeric())?;
+			checker.end(rest)?;
+		}
+		// A computer connected to itself would be its own neighbour, which the clique search can't cope with.
+		if a == b {
+			return Err(checker.error_at(b, ParseErrorKind::Unsupported));
+		}
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
+fn connections(input: &str) -> HashMap<[u8; 2], HashSet<[u8; 2]>> {
 	let mut connections = HashMap::with_capacity_and_hasher(520, FxBuildHasher);
 	for line in input.lines() {
 		let &[a1, a2, b'-', b1, b2] = line.as_bytes() else { unsafe { std::hint::unreachable_unchecked() } };
@@ -34,16 +319,42 @@ pub fn part2(input: &str) -> String {
 		connections.entry(b).or_insert_with(with_default_capacity).insert(a);
 	}
 	
+	connections
+}
+
+#[aoc(day23, part2)]
+pub fn part2(input: &str) -> String {
+	let graph = unsafe { LanGraph::parse(input).unwrap_unchecked() };
+	graph.names_of(&graph.maximum_clique())
+}
+
+#[allow(unused)]
+pub fn part2_backtracking(input: &str) -> String {
+	let largest = largest_network(&connections(input));
+	unsafe { String::from_utf8_unchecked(largest.join(&b',')) }
+}
+
+// Returns the computers in the largest fully-connected network, sorted.
+fn largest_network(connections: &HashMap<[u8; 2], HashSet<[u8; 2]>>) -> Vec<[u8; 2]> {
 	let mut largest = Vec::with_capacity(13);
 	let mut stack = Vec::with_capacity(12);
 	let mut seen = HashSet::with_capacity_and_hasher(connections.len(), FxBuildHasher);
-	for (&node, connected) in &connections {
-		inner_thing(&connections, node, &mut stack, filter(connected, |&pc| !seen.contains(pc)).copied(), &mut largest);
+	for (&node, connected) in connections {
+		inner_thing(connections, node, &mut stack, filter(connected, |&pc| !seen.contains(pc)).copied(), &mut largest);
 		seen.insert(node); // We've now checked every possible network containing 'node', so no need to include it in any future potential networks.
 	}
 	
 	largest.sort();
-	unsafe { String::from_utf8_unchecked(largest.join(&b',')) }
+	largest
+}
+
+pub fn try_part2(input: &str) -> Result<String, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
+// Renders the connections as a Graphviz graph, with the network 'part2' finds highlighted.
+pub fn to_dot(input: &str) -> Result<String, ParseError> {
+	LanGraph::try_parse(input).map(|graph| graph.to_dot())
 }
 
 fn inner_thing<I: Iterator<Item = [u8; 2]> + Clone>(connections: &HashMap<[u8; 2], HashSet<[u8; 2]>>,
@@ -71,3 +382,66 @@ fn all<I: IntoIterator, P: FnMut(I::Item) -> bool>(i: I, f: P) -> bool {
 fn filter<I: IntoIterator, P: FnMut(&I::Item) -> bool>(i: I, f: P) -> iter::Filter<I::IntoIter, P> {
 	i.into_iter().filter(f)
 }
+
+fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
+	i.into_iter().enumerate()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "kh-tc\nqp-kh\nde-cg\nka-co\nyn-aq\nqp-ub\ncg-tb\nvc-aq\ntb-ka\nwh-tc\nyn-cg\nkh-ub\nta-co\nde-co\ntc-td\ntb-wq\nwh-td\nta-ka\ntd-qp\naq-cg\nwq-ub\nub-vc\nde-ta\nwq-aq\nwq-vc\nwh-yn\nka-de\nkh-ta\nco-tc\nwh-qp\ntb-vc\ntd-yn\n";
+	
+	#[test]
+	fn example() {
+		let graph = LanGraph::parse(EXAMPLE).unwrap();
+		assert_eq!(graph.cliques_of_size(3, |_| true).len(), 12);
+		
+		let with_t = graph.cliques_of_size(3, |clique| clique.iter().any(|&id| graph.name(id).starts_with('t')));
+		let mut triangles: Vec<String> = with_t.iter().map(|clique| graph.names_of(clique)).collect();
+		triangles.sort_unstable();
+		assert_eq!(triangles, ["co,de,ta", "co,ka,ta", "de,ka,ta", "qp,td,wh", "tb,vc,wq", "tc,td,wh", "td,wh,yn"]);
+		
+		assert_eq!(graph.names_of(&graph.maximum_clique()), "co,de,ka,ta");
+		assert_eq!((try_part1(EXAMPLE), try_part2(EXAMPLE).as_deref()), (Ok(7), Ok("co,de,ka,ta")));
+	}
+	
+	#[test]
+	fn maximum_clique_ties() {
+		// Two triangles joined by a single edge; ids follow first appearance, so the triangle listed first wins.
+		let graph = LanGraph::parse("xa-xb\nxb-xc\nxc-xa\nab-cd\ncd-ef\nef-ab\nxa-ab\n").unwrap();
+		assert_eq!(graph.maximum_clique(), [0, 1, 2]);
+		assert_eq!(graph.names_of(&graph.maximum_clique()), "xa,xb,xc");
+		
+		let graph = LanGraph::parse("ab-cd\ncd-ef\nef-ab\nxa-xb\nxb-xc\nxc-xa\nxa-ab\n").unwrap();
+		assert_eq!(graph.names_of(&graph.maximum_clique()), "ab,cd,ef");
+		
+		let mut seed = 0x9e3779b97f4a7c15u64;
+		let mut random = |n: usize| {
+			seed ^= seed << 13;
+			seed ^= seed >> 7;
+			seed ^= seed << 17;
+			seed as usize % n
+		};
+		
+		for _ in 0..200 {
+			let nodes = 2 + random(14);
+			let mut input = String::new();
+			for a in 0..nodes {
+				for b in a + 1..nodes {
+					if random(2) == 0 {
+						let _ = writeln!(input, "{}z-{}z", (b'a' + a as u8) as char, (b'a' + b as u8) as char);
+					}
+				}
+			}
+			
+			let graph = LanGraph::parse(&input).unwrap();
+			let mut cliques = graph.maximal_cliques();
+			cliques.iter_mut().for_each(|clique| clique.sort_unstable());
+			let largest = cliques.iter().map(Vec::len).max().unwrap_or(0);
+			let expected = cliques.into_iter().filter(|clique| clique.len() == largest).min().unwrap_or_default();
+			assert_eq!(graph.maximum_clique(), expected, "\n{input}");
+		}
+	}
+}
@@ -1,7 +1,46 @@
+use std::{fmt::{self, Write as _}, iter, mem};
+
 use rustc_hash::{FxBuildHasher, FxHashMap as HashMap, FxHashSet as HashSet};
 
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
 #[derive(Copy, Eq, Clone, PartialEq, Debug)]
-enum Operator { And, Or, Xor }
+pub enum Operator { And, Or, Xor }
+
+impl Operator {
+	pub fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"AND" => Some(Self::And),
+			"OR" => Some(Self::Or),
+			"XOR" => Some(Self::Xor),
+			_ => None
+		}
+	}
+	
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::And => "AND",
+			Self::Or => "OR",
+			Self::Xor => "XOR"
+		}
+	}
+	
+	fn dot_colour(self) -> &'static str {
+		match self {
+			Self::And => "palegreen",
+			Self::Or => "lightskyblue",
+			Self::Xor => "khaki"
+		}
+	}
+	
+	pub fn apply(self, a: bool, b: bool) -> bool {
+		match self {
+			Self::And => a && b,
+			Self::Or => a || b,
+			Self::Xor => a ^ b
+		}
+	}
+}
 
 #[derive(Copy, Eq, Clone, PartialEq, Debug)]
 struct Gate {
@@ -44,6 +83,15 @@ fn get(id: [u8; 3], gates: &HashMap<[u8; 3], Gate>, values: &mut HashMap<[u8; 3]
 
 #[aoc(day24, part1)]
 pub fn part1(input: &str) -> u128 {
+	let circuit = unsafe { Circuit::parse(input).unwrap_unchecked() };
+	match circuit.evaluate() {
+		Ok(values) => circuit.read_bus(&values, "z"),
+		Err(_) => unreachable!()
+	}
+}
+
+#[allow(unused)]
+pub fn part1_recursive(input: &str) -> u128 {
 	let mut gates = HashMap::default();
 	let mut values = HashMap::default();
 	
@@ -115,6 +163,317 @@ pub fn part1(input: &str) -> u128 {
 	output
 }
 
+fn check_wire<'a>(checker: Checker, s: &'a str) -> Result<&'a str, ParseError> {
+	let (name, rest) = s.split_at_checked(3).ok_or_else(|| checker.error_at_end(s, ParseErrorKind::UnexpectedEnd))?;
+	checker.bytes_in(name, |b| b.is_ascii_alphanumeric())?;
+	Ok(rest)
+}
+
+// Any run of alphanumerics and underscores, as accepted by 'Circuit'.
+fn check_name<'a>(checker: Checker, s: &'a str) -> Result<&'a str, ParseError> {
+	let len = s.bytes().take_while(|&b| b.is_ascii_alphanumeric() || b == b'_').count();
+	if len == 0 {
+		return Err(checker.error_at(s, s.bytes().next().map_or(ParseErrorKind::UnexpectedEnd, ParseErrorKind::UnexpectedByte)));
+	}
+	
+	Ok(&s[len..])
+}
+
+fn check<'a>(input: &'a str, check_wire: impl Fn(Checker<'a>, &'a str) -> Result<&'a str, ParseError>) -> Result<(), ParseError> {
+	let checker = Checker::new(24, input);
+	let mut lines = input.lines();
+	
+	while let Some(line) = lines.next().filter(|l| !l.is_empty()) {
+		let value = checker.prefix(check_wire(checker, line)?, ": ")?;
+		match *value.as_bytes() {
+			[b'0' | b'1'] => {},
+			[] => return Err(checker.error_at(value, ParseErrorKind::UnexpectedEnd)),
+			[b'0' | b'1', b, ..] => return Err(checker.error_at(&value[1..], ParseErrorKind::UnexpectedByte(b))),
+			[b, ..] => return Err(checker.error_at(value, ParseErrorKind::UnexpectedByte(b)))
+		}
+	}
+	
+	for line in lines {
+		let rest = checker.prefix(check_wire(checker, line)?, " ")?;
+		let rest = ["AND ", "OR ", "XOR "].into_iter().find_map(|op| rest.strip_prefix(op))
+				.ok_or_else(|| checker.error_at(rest, rest.bytes().next().map_or(ParseErrorKind::UnexpectedEnd, ParseErrorKind::UnexpectedByte)))?;
+		let rest = checker.prefix(check_wire(checker, rest)?, " -> ")?;
+		checker.end(check_wire(checker, rest)?)?;
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u128, ParseError> {
+	check(input, check_wire)?;
+	let checker = Checker::new(24, input);
+	
+	let circuit = Circuit::try_parse(input)?;
+	match circuit.evaluate() {
+		Ok(values) => Ok(circuit.read_bus(&values, "z")),
+		Err(CircuitError::Undriven(name)) => {
+			let used = input.lines().flat_map(|line| line.split(' ')).find(|&word| word == name).unwrap_or(input);
+			Err(checker.error_at(used, ParseErrorKind::Missing(name.as_bytes()[0])))
+		},
+		Err(_) => Err(checker.error_at_end(input, ParseErrorKind::Unsolvable))
+	}
+}
+
+pub type WireId = usize;
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum Driver {
+	Undriven,
+	Fixed(bool),
+	Gate{ a: WireId, op: Operator, b: WireId }
+}
+
+#[derive(Eq, Clone, PartialEq, Debug)]
+pub enum CircuitError {
+	Redefined(String),
+	Undriven(String),
+	Cycle(Vec<String>), // Each wire is an input to the gate driving the next one, and the last one feeds the first.
+	BusMismatch // The x, y and z buses aren't shaped like an adder's.
+}
+
+impl fmt::Display for CircuitError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Redefined(name) => write!(f, "wire '{name}' is driven more than once"),
+			Self::Undriven(name) => write!(f, "wire '{name}' is used but never driven"),
+			Self::Cycle(names) => write!(f, "wires form a cycle: {}", names.join(" -> ")),
+			Self::BusMismatch => write!(f, "x and y must be buses of the same width n starting from bit 0, and z must have n + 1 bits")
+		}
+	}
+}
+
+// A netlist of named wires, each either undriven, fixed to a value, or the output of a single gate.
+#[derive(Clone, Default)]
+pub struct Circuit {
+	names: Vec<String>,
+	ids: HashMap<String, WireId>,
+	drivers: Vec<Driver>
+}
+
+impl Circuit {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	
+	// Accepts "name: 0" or "name: 1" for fixed wires and "a OP b -> out" for gates, in any order.
+	pub fn parse(input: &str) -> Option<Self> {
+		let mut circuit = Self::new();
+		for line in input.lines().filter(|l| !l.is_empty()) {
+			circuit.add_line(line)?.ok()?;
+		}
+		
+		Some(circuit)
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		let checker = Checker::new(24, input);
+		check(input, check_name)?;
+		
+		let mut circuit = Self::new();
+		for line in input.lines().filter(|l| !l.is_empty()) {
+			if unsafe { circuit.add_line(line).unwrap_unchecked() }.is_err() {
+				return Err(checker.error_at(line, ParseErrorKind::Redefined));
+			}
+		}
+		
+		Ok(circuit)
+	}
+	
+	fn add_line(&mut self, line: &str) -> Option<Result<(), CircuitError>> {
+		if let Some((name, value)) = line.split_once(": ") {
+			let value = match value { "0" => false, "1" => true, _ => return None };
+			return Some(self.set_fixed(name, value));
+		}
+		
+		let mut parts = line.split(' ');
+		let [a, op, b, "->", out] = parts.next_chunk().ok()? else {
+			return None;
+		};
+		if [a, b, out].iter().any(|name| name.is_empty()) || parts.next().is_some() {
+			return None;
+		}
+		
+		Some(self.add_gate(out, a, Operator::from_name(op)?, b))
+	}
+	
+	// Returns the id of the wire with this name, adding it (undriven) if there isn't one yet.
+	pub fn wire(&mut self, name: &str) -> WireId {
+		if let Some(&id) = self.ids.get(name) {
+			return id;
+		}
+		
+		let id = self.names.len();
+		self.names.push(name.into());
+		self.ids.insert(name.into(), id);
+		self.drivers.push(Driver::Undriven);
+		id
+	}
+	
+	pub fn id(&self, name: &str) -> Option<WireId> {
+		self.ids.get(name).copied()
+	}
+	
+	pub fn name(&self, id: WireId) -> &str {
+		&self.names[id]
+	}
+	
+	pub fn driver(&self, id: WireId) -> Driver {
+		self.drivers[id]
+	}
+	
+	pub fn wire_count(&self) -> usize {
+		self.names.len()
+	}
+	
+	fn drive(&mut self, out: &str, driver: Driver) -> Result<(), CircuitError> {
+		let id = self.wire(out);
+		match self.drivers[id] {
+			Driver::Undriven => {
+				self.drivers[id] = driver;
+				Ok(())
+			},
+			_ => Err(CircuitError::Redefined(out.into()))
+		}
+	}
+	
+	pub fn set_fixed(&mut self, name: &str, value: bool) -> Result<(), CircuitError> {
+		self.drive(name, Driver::Fixed(value))
+	}
+	
+	pub fn add_gate(&mut self, out: &str, a: &str, op: Operator, b: &str) -> Result<(), CircuitError> {
+		let (a, b) = (self.wire(a), self.wire(b));
+		self.drive(out, Driver::Gate{ a, op, b })
+	}
+	
+	// The wires named 'prefix' followed by a decimal bit index (e.g. "x00", "x01", ...), as '(bit, id)' pairs in order of bit.
+	pub fn bus(&self, prefix: &str) -> Vec<(u32, WireId)> {
+		let mut bus: Vec<_> = enumerate(&self.names).filter_map(|(id, name)| {
+			let digits = name.strip_prefix(prefix).filter(|d| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()))?;
+			Some((digits.parse().ok()?, id))
+		}).collect();
+		
+		bus.sort_unstable();
+		bus
+	}
+	
+	// Fixes every wire on the bus to the corresponding bit of 'value'. Bits beyond the bus' width are ignored.
+	pub fn set_bus(&mut self, prefix: &str, value: u128) -> Result<(), CircuitError> {
+		for (bit, id) in self.bus(prefix) {
+			match self.drivers[id] {
+				Driver::Gate{ .. } => return Err(CircuitError::Redefined(self.names[id].clone())),
+				_ => self.drivers[id] = Driver::Fixed(bit < u128::BITS && value >> bit & 1 != 0)
+			}
+		}
+		
+		Ok(())
+	}
+	
+	// Reads the bus as a little-endian integer from the values 'evaluate' returned. Bits beyond 127 are ignored.
+	pub fn read_bus(&self, values: &[bool], prefix: &str) -> u128 {
+		self.bus(prefix).into_iter()
+				.filter(|&(bit, id)| bit < u128::BITS && values[id])
+				.fold(0, |acc, (bit, _)| acc | 1 << bit)
+	}
+	
+	// Orders the wires so every gate comes after both of its inputs.
+	pub fn topological_order(&self) -> Result<Vec<WireId>, CircuitError> {
+		let mut pending_inputs = vec![0u8; self.drivers.len()];
+		let mut fed = vec![Vec::new(); self.drivers.len()];
+		for (id, &driver) in enumerate(&self.drivers) {
+			if let Driver::Gate{ a, b, .. } = driver {
+				pending_inputs[id] = 2;
+				fed[a].push(id);
+				fed[b].push(id);
+			}
+		}
+		
+		let mut order: Vec<_> = (0..self.drivers.len()).filter(|&id| pending_inputs[id] == 0).collect();
+		let mut next = 0;
+		while let Some(&id) = order.get(next) {
+			next += 1;
+			for &to in &fed[id] {
+				pending_inputs[to] -= 1;
+				if pending_inputs[to] == 0 {
+					order.push(to);
+				}
+			}
+		}
+		
+		if order.len() == self.drivers.len() {
+			return Ok(order);
+		}
+		
+		// Every wire left over is a gate with a left-over input, so following those has to end up going in circles.
+		let mut at = unsafe { pending_inputs.iter().position(|&p| p != 0).unwrap_unchecked() };
+		let mut visited = vec![false; self.drivers.len()];
+		let mut path = Vec::new();
+		while !visited[at] {
+			visited[at] = true;
+			path.push(at);
+			let Driver::Gate{ a, b, .. } = self.drivers[at] else { unreachable!() };
+			at = if pending_inputs[a] != 0 { a } else { b };
+		}
+		
+		let start = unsafe { path.iter().position(|&id| id == at).unwrap_unchecked() };
+		Err(CircuitError::Cycle(path[start..].iter().rev().map(|&id| self.names[id].clone()).collect()))
+	}
+	
+	// Renders the netlist as a Graphviz digraph with one node per wire, coloured by the gate driving it.
+	// Wires named in 'suspect' (and everything they feed into) are outlined in red.
+	pub fn to_dot(&self, suspect: &[&str]) -> String {
+		let is_suspect = |id: WireId| suspect.contains(&self.names[id].as_str());
+		
+		let mut dot = String::with_capacity(self.names.len() * 64);
+		let _ = writeln!(dot, "digraph circuit {{");
+		let _ = writeln!(dot, "\trankdir=LR;");
+		let _ = writeln!(dot, "\tnode [style=filled, fillcolor=white];");
+		for (id, name) in enumerate(&self.names) {
+			let highlight = if is_suspect(id) { ", color=red, penwidth=3" } else { "" };
+			let _ = match self.drivers[id] {
+				Driver::Undriven => writeln!(dot, "\t{name:?} [shape=box, style=dashed{highlight}];"),
+				Driver::Fixed(value) => writeln!(dot, "\t{name:?} [shape=box, fillcolor=lightgrey, label=\"{}\\n{}\"{highlight}];", name.escape_default(), u8::from(value)),
+				Driver::Gate{ op, .. } => writeln!(dot, "\t{name:?} [fillcolor={}, label=\"{}\\n{}\"{highlight}];", op.dot_colour(), name.escape_default(), op.name())
+			};
+		}
+		
+		for (id, &driver) in enumerate(&self.drivers) {
+			if let Driver::Gate{ a, b, .. } = driver {
+				for input in [a, b] {
+					let highlight = if is_suspect(input) { " [color=red]" } else { "" };
+					let _ = writeln!(dot, "\t{:?} -> {:?}{highlight};", self.names[input], self.names[id]);
+				}
+			}
+		}
+		
+		dot.push_str("}\n");
+		dot
+	}
+	
+	// Returns the value of every wire, indexed by 'WireId'. Undriven wires are only an error if a gate uses them.
+	pub fn evaluate(&self) -> Result<Vec<bool>, CircuitError> {
+		let mut values = vec![false; self.drivers.len()];
+		for id in self.topological_order()? {
+			values[id] = match self.drivers[id] {
+				Driver::Undriven => false,
+				Driver::Fixed(value) => value,
+				Driver::Gate{ a, op, b } => {
+					if let Some(&undriven) = [a, b].iter().find(|&&input| self.drivers[input] == Driver::Undriven) {
+						return Err(CircuitError::Undriven(self.names[undriven].clone()));
+					}
+					op.apply(values[a], values[b])
+				}
+			};
+		}
+		
+		Ok(values)
+	}
+}
+
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum GetOr0Error {
 	LoopAt([u8; 3])
@@ -141,8 +500,241 @@ fn get_or_0(id: [u8; 3], gates: &HashMap<[u8; 3], Gate>, overrides: &HashMap<[u8
 	Ok(value)
 }
 
+// The part each gate plays in one bit of a ripple-carry adder:
+// 'HalfSum' = x XOR y, 'HalfCarry' = x AND y, 'Sum' = HalfSum XOR Carry (on z), 'CarryThrough' = HalfSum AND Carry,
+// and 'Carry' = HalfCarry OR CarryThrough, which is the carry into the next bit.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum AdderRole { HalfSum, HalfCarry, Sum, CarryThrough, Carry }
+
+impl fmt::Display for AdderRole {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			Self::HalfSum => "half sum",
+			Self::HalfCarry => "half carry",
+			Self::Sum => "sum",
+			Self::CarryThrough => "carry-through",
+			Self::Carry => "carry"
+		})
+	}
+}
+
+#[derive(Eq, Clone, PartialEq, Debug)]
+pub enum AdderFault {
+	// The gate for 'role' drives 'is' instead of 'should_be' (and the other way around).
+	Swapped{ role: AdderRole, should_be: String, is: String },
+	// No gate fits 'role', even allowing for swaps. Nothing after this is checked.
+	Missing(AdderRole)
+}
+
+impl fmt::Display for AdderFault {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Swapped{ role, should_be, is } => write!(f, "{role} should be on '{should_be}' but is on '{is}'"),
+			Self::Missing(role) => write!(f, "no gate can be the {role}")
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct AdderReport {
+	pub width: u32,
+	pub bits: Vec<Vec<AdderFault>> // Indexed by bit. The carry out of the top bit (onto its own z wire) counts as part of that bit.
+}
+
+impl AdderReport {
+	pub fn is_correct(&self) -> bool {
+		self.bits.iter().all(Vec::is_empty)
+	}
+	
+	// Every wire involved in a swap, sorted.
+	pub fn swapped_wires(&self) -> Vec<&str> {
+		let mut wires: Vec<&str> = self.bits.iter().flatten().filter_map(|fault| match fault {
+			AdderFault::Swapped{ should_be, is, .. } => Some([should_be.as_str(), is.as_str()]),
+			AdderFault::Missing(_) => None
+		}).flatten().collect();
+		
+		wires.sort_unstable();
+		wires.dedup();
+		wires
+	}
+}
+
+impl fmt::Display for AdderReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_correct() {
+			return writeln!(f, "{}-bit adder is wired correctly", self.width);
+		}
+		
+		for (bit, faults) in enumerate(&self.bits) {
+			for fault in faults {
+				writeln!(f, "bit {bit:>2}: {fault}")?;
+			}
+		}
+		
+		Ok(())
+	}
+}
+
+// Walks the adder one bit at a time, untangling swapped outputs as it finds them. Gates never change their inputs (only
+// which wire they drive), so each one is identified by its 'Driver' rather than the wire it's currently on.
+struct AdderWalk<'c> {
+	circuit: &'c Circuit,
+	drivers: Vec<Driver>,
+	faults: Vec<AdderFault>
+}
+
+impl AdderWalk<'_> {
+	fn out(&self, gate: Driver) -> WireId {
+		unsafe { self.drivers.iter().position(|&d| d == gate).unwrap_unchecked() }
+	}
+	
+	fn gate(&self, op: Operator, x: WireId, y: WireId) -> Option<Driver> {
+		self.drivers.iter().copied().find(|&d| matches!(d, Driver::Gate{ a, op: o, b } if o == op && (a, b) == (x, y) || o == op && (b, a) == (x, y)))
+	}
+	
+	// Returns the first gate of type 'op' that reads 'input', along with its other input.
+	fn reader(&self, op: Operator, input: WireId) -> Option<(Driver, WireId)> {
+		self.drivers.iter().find_map(|&d| match d {
+			Driver::Gate{ a, op: o, b } if o == op && a == input => Some((d, b)),
+			Driver::Gate{ a, op: o, b } if o == op && b == input => Some((d, a)),
+			_ => None
+		})
+	}
+	
+	fn inputs(gate: Driver) -> [WireId; 2] {
+		match gate {
+			Driver::Gate{ a, b, .. } => [a, b],
+			_ => unreachable!()
+		}
+	}
+	
+	fn swap(&mut self, role: AdderRole, should_be: WireId, is: WireId) {
+		self.drivers.swap(should_be, is);
+		self.faults.push(AdderFault::Swapped{ role, should_be: self.circuit.names[should_be].clone(), is: self.circuit.names[is].clone() });
+	}
+	
+	// Makes sure 'gate' reads both 'first' and 'second' (which are on the right wires) by moving whichever one it doesn't
+	// read onto the wire it reads instead. Fails if it reads neither.
+	fn connect(&mut self, gate: Driver, (first, first_role): (Driver, AdderRole), (second, second_role): (Driver, AdderRole)) -> Result<(), ()> {
+		let [a, b] = Self::inputs(gate);
+		let (first_out, second_out) = (self.out(first), self.out(second));
+		match (a == first_out || b == first_out, a == second_out || b == second_out) {
+			(true, true) => {},
+			(true, false) => self.swap(second_role, if a == first_out { b } else { a }, second_out),
+			(false, true) => self.swap(first_role, if a == second_out { b } else { a }, first_out),
+			(false, false) => return Err(())
+		}
+		
+		Ok(())
+	}
+	
+	// Returns the carry out of the bit, or the role it couldn't find a gate for.
+	fn bit(&mut self, x: WireId, y: WireId, z: WireId, next: Option<Driver>, carry_in: Option<Driver>) -> Result<Driver, AdderRole> {
+		let half_sum = self.gate(Operator::Xor, x, y).ok_or(AdderRole::HalfSum)?;
+		let half_carry = self.gate(Operator::And, x, y).ok_or(AdderRole::HalfCarry)?;
+		
+		let Some(carry_in) = carry_in else { // Bit 0 is just a half adder.
+			if self.out(half_sum) != z {
+				self.swap(AdderRole::Sum, z, self.out(half_sum));
+			}
+			return Ok(half_carry);
+		};
+		
+		// Any two of these agreeing is enough to go on.
+		let by_half_sum = self.reader(Operator::Xor, self.out(half_sum)).map(|(g, _)| g);
+		let by_carry = self.reader(Operator::Xor, self.out(carry_in)).map(|(g, _)| g);
+		let by_output = Some(self.drivers[z]).filter(|&d| matches!(d, Driver::Gate{ op: Operator::Xor, a, .. } if matches!(self.drivers[a], Driver::Gate{ .. })));
+		let sum = match (by_half_sum, by_carry, by_output) {
+			(Some(g), Some(h), _) | (Some(g), _, Some(h)) | (_, Some(g), Some(h)) if g == h => g,
+			(Some(g), None, None) | (None, Some(g), None) | (None, None, Some(g)) => g,
+			_ => return Err(AdderRole::Sum)
+		};
+		self.connect(sum, (half_sum, AdderRole::HalfSum), (carry_in, AdderRole::Carry)).map_err(|()| AdderRole::Sum)?;
+		if self.out(sum) != z {
+			self.swap(AdderRole::Sum, z, self.out(sum));
+		}
+		
+		let through = self.gate(Operator::And, self.out(half_sum), self.out(carry_in)).ok_or(AdderRole::CarryThrough)?;
+		
+		let by_half_carry = self.reader(Operator::Or, self.out(half_carry)).map(|(g, _)| g);
+		let by_through = self.reader(Operator::Or, self.out(through)).map(|(g, _)| g);
+		let carry = match (by_half_carry, by_through) {
+			(Some(g), Some(h)) if g == h => g,
+			(Some(g), None) | (None, Some(g)) => g,
+			(Some(g), Some(h)) => { // Only one of them can be the carry into the next bit (or the top bit of z).
+				let feeds_next = |walk: &Self, gate: Driver| match next {
+					Some(next_half_sum) => walk.reader(Operator::Xor, walk.out(gate)).is_some_and(|(_, other)| walk.drivers[other] == next_half_sum),
+					None => false
+				};
+				match (feeds_next(self, g), feeds_next(self, h)) {
+					(true, false) => g,
+					(false, true) => h,
+					_ => return Err(AdderRole::Carry)
+				}
+			},
+			(None, None) => return Err(AdderRole::Carry)
+		};
+		self.connect(carry, (half_carry, AdderRole::HalfCarry), (through, AdderRole::CarryThrough)).map_err(|()| AdderRole::Carry)?;
+		
+		Ok(carry)
+	}
+}
+
+impl Circuit {
+	// Checks the circuit is a ripple-carry adder computing z = x + y, bit by bit, reporting which outputs are swapped.
+	// Any number of swaps can be found, as long as there's enough left intact around each one to tell what it should be.
+	pub fn verify_adder(&self) -> Result<AdderReport, CircuitError> {
+		let (x, y, z) = (self.bus("x"), self.bus("y"), self.bus("z"));
+		let is_contiguous = |bus: &[(u32, WireId)]| enumerate(bus).all(|(i, &(bit, _))| i == bit as usize);
+		if x.is_empty() || x.len() != y.len() || z.len() != x.len() + 1 || !(is_contiguous(&x) && is_contiguous(&y) && is_contiguous(&z)) {
+			return Err(CircuitError::BusMismatch);
+		}
+		
+		let mut walk = AdderWalk { circuit: self, drivers: self.drivers.clone(), faults: Vec::new() };
+		let mut report = AdderReport { width: x.len() as u32, bits: Vec::with_capacity(x.len()) };
+		let mut carry = None;
+		
+		for bit in 0..x.len() {
+			let next = x.get(bit + 1).and_then(|&(_, next_x)| walk.gate(Operator::Xor, next_x, y[bit + 1].1));
+			let result = walk.bit(x[bit].1, y[bit].1, z[bit].1, next, carry);
+			report.bits.push(mem::take(&mut walk.faults));
+			match result {
+				Ok(c) => carry = Some(c),
+				Err(role) => {
+					report.bits[bit].push(AdderFault::Missing(role));
+					return Ok(report);
+				}
+			}
+		}
+		
+		let (carry_out, z_top) = (walk.out(unsafe { carry.unwrap_unchecked() }), z[x.len()].1);
+		if carry_out != z_top {
+			walk.swap(AdderRole::Carry, z_top, carry_out);
+			report.bits[x.len() - 1].append(&mut walk.faults);
+		}
+		
+		Ok(report)
+	}
+}
+
 #[aoc(day24, part2)]
 pub fn part2(input: &str) -> String {
+	let circuit = unsafe { Circuit::parse(input).unwrap_unchecked() };
+	match circuit.verify_adder() {
+		Ok(report) => report.swapped_wires().join(","),
+		Err(_) => unreachable!()
+	}
+}
+
+// Renders the circuit (see 'Circuit::to_dot') with the wires 'verify_adder' finds swapped highlighted.
+pub fn to_dot(input: &str) -> Result<String, ParseError> {
+	let circuit = Circuit::try_parse(input)?;
+	let report = circuit.verify_adder();
+	Ok(circuit.to_dot(report.as_ref().map(AdderReport::swapped_wires).as_deref().unwrap_or_default()))
+}
+
+#[allow(unused)]
+pub fn part2_swap_search(input: &str) -> String {
 	let mut gates = HashMap::default();
 	let mut values = HashMap::default();
 	
@@ -262,6 +854,20 @@ pub fn part2(input: &str) -> String {
 	unsafe { String::from_utf8_unchecked(swapping.join(&b',')) }
 }
 
+pub fn try_part2(input: &str) -> Result<String, ParseError> {
+	check(input, check_wire)?;
+	let checker = Checker::new(24, input);
+	let unsupported = || checker.error_at_end(input, ParseErrorKind::Unsupported);
+	
+	let circuit = Circuit::try_parse(input)?;
+	let report = circuit.verify_adder().map_err(|_| unsupported())?;
+	if report.bits.iter().flatten().any(|fault| matches!(fault, AdderFault::Missing(_))) {
+		return Err(unsupported());
+	}
+	
+	Ok(report.swapped_wires().join(","))
+}
+
 fn test_thing(gates: &HashMap<[u8; 3], Gate>, overrides: &HashMap<[u8; 3], Gate>, values: &mut HashMap<[u8; 3], bool>, seen: &mut HashSet<[u8; 3]>) -> bool {
 	for kind in ['x', 'y'] {
 		// println!("{kind}:");
@@ -430,3 +1036,78 @@ fn test_thing(gates: &HashMap<[u8; 3], Gate>, overrides: &HashMap<[u8; 3], Gate>
 	
 	true
 }
+
+fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
+	i.into_iter().enumerate()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	// A 'width'-bit ripple-carry adder in the usual shape, with the outputs of each pair in 'swaps' exchanged.
+	fn adder(width: u32, swaps: &[(&str, &str)]) -> String {
+		let mut text = String::new();
+		for bit in 0..width {
+			let _ = writeln!(text, "x{bit:02}: 0\ny{bit:02}: 0");
+		}
+		text.push('\n');
+		
+		let out = |name: String| swaps.iter().find_map(|&(a, b)| {
+			if a == name { Some(b.to_owned()) } else if b == name { Some(a.to_owned()) } else { None }
+		}).unwrap_or(name);
+		let carry = |bit: u32| if bit + 1 == width { format!("z{width:02}") } else { format!("c{bit:02}") };
+		
+		let _ = writeln!(text, "x00 XOR y00 -> {}\nx00 AND y00 -> {}", out("z00".into()), out(carry(0)));
+		for bit in 1..width {
+			let (hs, hc, ct, previous) = (format!("hs{bit:02}"), format!("hc{bit:02}"), format!("ct{bit:02}"), carry(bit - 1));
+			let _ = writeln!(text, "x{bit:02} XOR y{bit:02} -> {}", out(hs.clone()));
+			let _ = writeln!(text, "x{bit:02} AND y{bit:02} -> {}", out(hc.clone()));
+			let _ = writeln!(text, "{hs} XOR {previous} -> {}", out(format!("z{bit:02}")));
+			let _ = writeln!(text, "{hs} AND {previous} -> {}", out(ct.clone()));
+			let _ = writeln!(text, "{hc} OR {ct} -> {}", out(carry(bit)));
+		}
+		
+		text
+	}
+	
+	fn swapped_wires(width: u32, swaps: &[(&str, &str)]) -> Vec<String> {
+		let circuit = Circuit::parse(&adder(width, swaps)).unwrap();
+		circuit.verify_adder().unwrap().swapped_wires().into_iter().map(String::from).collect()
+	}
+	
+	#[test]
+	fn correct_adder_adds() {
+		let mut circuit = Circuit::parse(&adder(8, &[])).unwrap();
+		assert!(circuit.verify_adder().unwrap().is_correct());
+		
+		for (x, y) in [(0, 0), (1, 255), (200, 100), (255, 255)] {
+			circuit.set_bus("x", x).unwrap();
+			circuit.set_bus("y", y).unwrap();
+			assert_eq!(circuit.read_bus(&circuit.evaluate().unwrap(), "z"), x + y);
+		}
+	}
+	
+	#[test]
+	fn finds_swaps_between_roles() {
+		assert_eq!(swapped_wires(8, &[("hs03", "hc03")]), ["hc03", "hs03"]);
+		assert_eq!(swapped_wires(8, &[("z04", "ct04")]), ["ct04", "z04"]);
+		assert_eq!(swapped_wires(8, &[("z05", "c05")]), ["c05", "z05"]);
+		assert_eq!(swapped_wires(8, &[("z00", "c00")]), ["c00", "z00"]);
+		assert_eq!(swapped_wires(8, &[("z07", "z08")]), ["z07", "z08"]);
+	}
+	
+	#[test]
+	fn finds_several_swaps() {
+		let swaps = [("hs01", "hc01"), ("z03", "c03"), ("z05", "ct05"), ("hc06", "z06")];
+		let mut expected: Vec<_> = swaps.iter().flat_map(|&(a, b)| [a, b]).collect();
+		expected.sort_unstable();
+		assert_eq!(swapped_wires(8, &swaps), expected);
+	}
+	
+	#[test]
+	fn rejects_mismatched_buses() {
+		let circuit = Circuit::parse("x00: 0\nx01: 0\ny00: 0\n\nx00 XOR y00 -> z00\nx00 AND y00 -> z01\n").unwrap();
+		assert_eq!(circuit.verify_adder().unwrap_err(), CircuitError::BusMismatch);
+	}
+}
@@ -1,6 +1,8 @@
 // #![feature(iter_next_chunk)] (v1 only)
 use std::iter;
 
+use crate::error::{self, Checker, ParseErrorKind};
+
 const A: usize = "#####\n#####\n#####\n#####\n#####\n#####".len();
 const B: usize = "\n.....\n\n".len();
 
@@ -154,6 +156,51 @@ fn parse_lock_or_key<T: AsRef<[u8]>>(input_lines: &[T; 7]) -> Result<Parsed, Par
 	})
 }
 
+fn check(input: &str) -> Result<(), error::ParseError> {
+	let checker = Checker::new(25, input);
+	// 'part1' skips over the input in fixed-size steps, so this can't use 'lines' (which would also accept "\r\n").
+	let mut lines = input.strip_suffix('\n').unwrap_or(input).split('\n');
+	
+	loop {
+		let chunk: [&str; 7] = match lines.next_chunk() {
+			Ok(chunk) => chunk,
+			Err(partial) => match partial.last() {
+				Some(last) => return Err(checker.error_at_end(last, ParseErrorKind::UnexpectedEnd)),
+				None => return Ok(())
+			}
+		};
+		
+		if let Err(e) = parse_lock_or_key(&chunk) {
+			let byte_at = |line_index: u8, byte_index: u8| checker.offset_of(chunk[line_index as usize]) + byte_index as usize;
+			return Err(match e {
+				ParseError::WrongLen{ line_index } => {
+					let line = chunk[line_index as usize];
+					match line.as_bytes().get(5) {
+						Some(&b) => checker.error_at_offset(byte_at(line_index, 5), ParseErrorKind::UnexpectedByte(b)),
+						None => checker.error_at_end(line, ParseErrorKind::UnexpectedEnd)
+					}
+				},
+				ParseError::InvalidChar{ line_index, byte_index } => {
+					let b = chunk[line_index as usize].as_bytes()[byte_index as usize];
+					checker.error_at_offset(byte_at(line_index, byte_index), ParseErrorKind::UnexpectedByte(b))
+				},
+				ParseError::Hole{ line_index, byte_index } => checker.error_at_offset(byte_at(line_index, byte_index), ParseErrorKind::Unsupported),
+				ParseError::TopNotUniform => checker.error_at(chunk[0], ParseErrorKind::Unsupported),
+				ParseError::BottomNotUniform | ParseError::BottomMatchesTop => checker.error_at(chunk[6], ParseErrorKind::Unsupported)
+			});
+		}
+		
+		match lines.next() {
+			Some(separator) => checker.end(separator)?,
+			None => return Ok(())
+		}
+	}
+}
+
+pub fn try_part1(input: &str) -> Result<usize, error::ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 #[allow(unused)]
 pub fn part1_v1(input: &str) -> usize {
 	let mut locks = Vec::with_capacity(250);
@@ -181,3 +228,7 @@ fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 
 #[allow(unused)]
 pub fn part2(_: &str) -> &'static str { "" }
+
+pub fn try_part2(input: &str) -> Result<&'static str, error::ParseError> {
+	Ok(part2(input))
+}
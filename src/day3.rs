@@ -1,5 +1,149 @@
-#[aoc(day3, part1)] // I did not bother optimizing this at all and will be amazed if it benchmarks well.
+use std::io::{self, Read};
+
+use crate::error::ParseError;
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum Token {
+	Mul(u16, u16),
+	Do,
+	Dont
+}
+
+impl Token {
+	fn keyword(self) -> &'static [u8] {
+		match self {
+			Self::Mul(..) => b"mul(",
+			Self::Do => b"do()",
+			Self::Dont => b"don't()"
+		}
+	}
+}
+
+#[derive(Copy, Eq, Clone, PartialEq, Default, Debug)]
+enum State {
+	#[default]
+	Start,
+	// Part way through the keyword of 'token' (which for 'mul' is just the bit up to the opening bracket).
+	Keyword{ token: Token, matched: u8 },
+	First{ value: u16, digits: u8 },
+	Second{ first: u16, value: u16, digits: u8 }
+}
+
+// Recognises tokens one byte at a time, so input can be fed in however it arrives. Anything else is skipped over.
+#[derive(Clone, Default, Debug)]
+pub struct Tokenizer {
+	state: State
+}
+
+impl Tokenizer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	
+	fn step(state: State, b: u8) -> (State, Option<Token>) {
+		match (state, b) {
+			(State::Start, b'm') => (State::Keyword{ token: Token::Mul(0, 0), matched: 1 }, None),
+			(State::Start, b'd') => (State::Keyword{ token: Token::Do, matched: 1 }, None),
+			(State::Keyword{ token: Token::Do, matched: 2 }, b'n') => (State::Keyword{ token: Token::Dont, matched: 3 }, None),
+			(State::Keyword{ token, matched }, b) if token.keyword()[matched as usize] == b => {
+				let matched = matched + 1;
+				match token {
+					_ if (matched as usize) < token.keyword().len() => (State::Keyword{ token, matched }, None),
+					Token::Mul(..) => (State::First{ value: 0, digits: 0 }, None),
+					_ => (State::Start, Some(token))
+				}
+			},
+			(State::First{ value, digits }, b'0'..=b'9') if digits < 3 => (State::First{ value: value * 10 + (b - b'0') as u16, digits: digits + 1 }, None),
+			(State::First{ value, digits }, b',') if digits > 0 => (State::Second{ first: value, value: 0, digits: 0 }, None),
+			(State::Second{ first, value, digits }, b'0'..=b'9') if digits < 3 => {
+				(State::Second{ first, value: value * 10 + (b - b'0') as u16, digits: digits + 1 }, None)
+			},
+			(State::Second{ first, value, digits }, b')') if digits > 0 => (State::Start, Some(Token::Mul(first, value))),
+			_ => (State::Start, None)
+		}
+	}
+	
+	pub fn push(&mut self, b: u8) -> Option<Token> {
+		let (state, token) = match Self::step(self.state, b) {
+			// No keyword contains the first letter of any other past its own start, so this is the only place the next token could begin.
+			(State::Start, None) if self.state != State::Start => Self::step(State::Start, b),
+			stepped => stepped
+		};
+		
+		self.state = state;
+		token
+	}
+	
+	// Tokens completed by 'chunk'. A token can start in one chunk and end in a later one.
+	pub fn feed<'a>(&'a mut self, chunk: &'a [u8]) -> impl Iterator<Item = Token> + 'a {
+		chunk.iter().filter_map(|&b| self.push(b))
+	}
+}
+
+pub fn tokens(input: &str) -> impl Iterator<Item = Token> + '_ {
+	let mut tokenizer = Tokenizer::new();
+	input.bytes().filter_map(move |b| tokenizer.push(b))
+}
+
+pub struct ReadTokens<R> {
+	reader: R,
+	buffer: Box<[u8]>,
+	position: usize,
+	filled: usize,
+	tokenizer: Tokenizer
+}
+
+// Reads in chunks of 'chunk_size' bytes.
+pub fn read_tokens<R: Read>(reader: R, chunk_size: usize) -> ReadTokens<R> {
+	assert!(chunk_size > 0, "chunks must not be empty");
+	ReadTokens { reader, buffer: vec![0; chunk_size].into_boxed_slice(), position: 0, filled: 0, tokenizer: Tokenizer::new() }
+}
+
+impl<R: Read> Iterator for ReadTokens<R> {
+	type Item = io::Result<Token>;
+	
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			while let Some(&b) = self.buffer[..self.filled].get(self.position) {
+				self.position += 1;
+				if let Some(token) = self.tokenizer.push(b) {
+					return Some(Ok(token));
+				}
+			}
+			
+			match self.reader.read(&mut self.buffer) {
+				Ok(0) => return None,
+				Ok(filled) => (self.position, self.filled) = (0, filled),
+				Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+				Err(e) => return Some(Err(e))
+			}
+		}
+	}
+}
+
+// With 'conditional', 'mul's between a 'don't()' and the next 'do()' are skipped.
+pub fn sum_products(tokens: impl IntoIterator<Item = Token>, conditional: bool) -> u64 {
+	let mut enabled = true;
+	let mut total = 0;
+	for token in tokens {
+		match token {
+			Token::Mul(a, b) if enabled || !conditional => total += a as u64 * b as u64,
+			Token::Mul(..) => (),
+			Token::Do => enabled = true,
+			Token::Dont => enabled = false
+		}
+	}
+	
+	total
+}
+
+#[aoc(day3, part1)]
 pub fn part1(input: &str) -> u64 {
+	sum_products(tokens(input), false)
+}
+
+#[allow(unused)] // I did not bother optimizing this at all and will be amazed if it benchmarks well.
+pub fn part1_v1(input: &str) -> u64 {
 	let mut total = 0;
 	for candidate in input.split("mul(").skip(1) {
 		if let Some((first, rest)) = candidate.split_once(',') {
@@ -15,19 +159,126 @@ pub fn part1(input: &str) -> u64 {
 	total
 }
 
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+	Ok(part1(input)) // Anything that isn't a well-formed 'mul(a,b)' is just ignored, so there's nothing that can fail to parse.
+}
+
 #[aoc(day3, part2)]
 pub fn part2(input: &str) -> u64 {
+	sum_products(tokens(input), true)
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u64 {
 	let mut total = 0;
 	let mut remaining = input;
 	
 	while !remaining.is_empty() {
 		let (yes, no) = remaining.split_once("don't()").unwrap_or((remaining, ""));
 		
-		total += part1(yes);
+		total += part1_v1(yes);
 		
 		let (_, yes) = no.split_once("do()").unwrap_or((no, ""));
 		remaining = yes;
 	}
 	
 	total
+}
+
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+	Ok(part2(input))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+	
+	// Hands out at most 'limit' bytes per read, however big the buffer is.
+	struct Trickle<'a> {
+		bytes: &'a [u8],
+		limit: usize
+	}
+	
+	impl Read for Trickle<'_> {
+		fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+			let len = buffer.len().min(self.limit).min(self.bytes.len());
+			buffer[..len].copy_from_slice(&self.bytes[..len]);
+			self.bytes = &self.bytes[len..];
+			Ok(len)
+		}
+	}
+	
+	// Tries every keyword at every position.
+	fn tokens_by_search(input: &[u8]) -> Vec<Token> {
+		fn number(bytes: &[u8]) -> Option<(u16, &[u8])> {
+			let digits = bytes.iter().take(3).take_while(|b| b.is_ascii_digit()).count();
+			let value = bytes[..digits].iter().fold(0, |acc, &b| acc * 10 + (b - b'0') as u16);
+			(digits > 0).then(|| (value, &bytes[digits..]))
+		}
+		
+		(0..input.len()).filter_map(|i| {
+			let rest = &input[i..];
+			if rest.starts_with(b"do()") {
+				Some(Token::Do)
+			} else if rest.starts_with(b"don't()") {
+				Some(Token::Dont)
+			} else {
+				let (a, rest) = number(rest.strip_prefix(b"mul(")?)?;
+				let (b, rest) = number(rest.strip_prefix(b",")?)?;
+				rest.starts_with(b")").then_some(Token::Mul(a, b))
+			}
+		}).collect()
+	}
+	
+	#[test]
+	fn example() {
+		assert_eq!(tokens(EXAMPLE).collect::<Vec<_>>(), [Token::Mul(2, 4), Token::Dont, Token::Mul(5, 5), Token::Mul(11, 8), Token::Do, Token::Mul(8, 5)]);
+		assert_eq!(sum_products(tokens(EXAMPLE), false), 161);
+		assert_eq!(sum_products(tokens(EXAMPLE), true), 48);
+	}
+	
+	#[test]
+	fn malformed_muls() {
+		assert_eq!(tokens("mul(1234,5)mul(,5)mul(5,)mul (1,2)mul(1,2").count(), 0);
+		assert_eq!(tokens("mmul(1,2)mul(mul(3,4)mul(999,999)").collect::<Vec<_>>(), [Token::Mul(1, 2), Token::Mul(3, 4), Token::Mul(999, 999)]);
+		assert_eq!(tokens("dodo()don'tdon't()do(").collect::<Vec<_>>(), [Token::Do, Token::Dont]);
+	}
+	
+	#[test]
+	fn same_tokens_across_chunk_boundaries() {
+		let expected: Vec<_> = tokens(EXAMPLE).collect();
+		for split in 0..=EXAMPLE.len() {
+			let mut tokenizer = Tokenizer::new();
+			let mut fed: Vec<_> = tokenizer.feed(&EXAMPLE.as_bytes()[..split]).collect();
+			fed.extend(tokenizer.feed(&EXAMPLE.as_bytes()[split..]));
+			assert_eq!(fed, expected, "split at {split}");
+		}
+		
+		for chunk_size in 1..=EXAMPLE.len() + 1 {
+			for limit in [1, 2, 7, usize::MAX] {
+				let read: Vec<_> = read_tokens(Trickle { bytes: EXAMPLE.as_bytes(), limit }, chunk_size).map(Result::unwrap).collect();
+				assert_eq!(read, expected, "chunks of {chunk_size}, reads of up to {limit}");
+			}
+		}
+	}
+	
+	#[test]
+	fn matches_search() {
+		const ALPHABET: &[u8] = b"mul(),0129don't()x";
+		let mut seed = 0x9e3779b97f4a7c15u64;
+		let mut random = |n: usize| {
+			seed ^= seed << 13;
+			seed ^= seed >> 7;
+			seed ^= seed << 17;
+			seed as usize % n
+		};
+		
+		for _ in 0..2000 {
+			let input: Vec<u8> = (0..random(60)).map(|_| ALPHABET[random(ALPHABET.len())]).collect();
+			let mut tokenizer = Tokenizer::new();
+			assert_eq!(tokenizer.feed(&input).collect::<Vec<_>>(), tokens_by_search(&input), "{}", input.escape_ascii());
+		}
+	}
 }
\ No newline at end of file
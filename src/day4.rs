@@ -1,112 +1,7 @@
-use std::num::NonZero;
-use std::{marker::PhantomData, ptr::NonNull, slice};
-
-#[derive(Copy, Clone)]
-struct GridRefWithLn<'a, T> {
-	grid_ptr: NonNull<T>,
-	total_length_with_ln: NonZero<usize>,
-	row_length_with_ln: NonZero<usize>,
-	marker: PhantomData<&'a [T]>
-}
-
-impl<'a, T> GridRefWithLn<'a, T> {
-	pub fn from_slice(grid: &'a [T], row_length: NonZero<usize>) -> Option<Self> {
-		let row_length_with_ln = row_length.checked_add(1)?;
-		let total_length_with_ln = NonZero::new(
-			if grid.len() % row_length_with_ln == 0 {
-				grid.len()
-			} else {
-				grid.len() + 1
-			}
-		)?;
-		
-		(total_length_with_ln.get() % row_length_with_ln == 0).then(||
-			Self {
-				grid_ptr: unsafe { NonNull::new_unchecked(grid.as_ptr() as *mut _) },
-				total_length_with_ln,
-				row_length_with_ln,
-				marker: PhantomData
-			}
-		)
-	}
-	
-//	pub unsafe fn from_slice_unchecked(grid: &'a [T], row_length: NonZero<usize>) -> Self {
-//		Self { grid, row_length }
-//	}
-//	
-//	pub unsafe fn as_vec_mut(&mut self) -> &mut Vec<T> {
-//		&mut self.grid
-//	}
-	
-	
-	pub fn as_slice(&self) -> &'a [T] { // Does not include the final possibly-uninitialized '\n'
-		unsafe { slice::from_raw_parts(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
-	}
-	
-//	pub fn slice_mut(&mut self) -> &mut [T] {
-//		self.grid
-//	}
-	
-	
-	#[allow(unused)]
-	pub fn get(&self, row: usize, col: usize) -> Option<&'a T> {
-		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
-	}
-	
-//	#[allow(unused)]
-//	pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
-//		self.to_index(row, col).map(|i| unsafe { self.slice_mut().get_unchecked_mut(i) })
-//	}
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &'a T {
-		unsafe { self.as_slice().get_unchecked(self.to_index_unchecked(row, col)) }
-	}
-	
-//	#[allow(unused)]
-//	pub unsafe fn get_unchecked_mut(&mut self, row: usize, col: usize) -> &mut T {
-//		let index = self.to_index_unchecked(row, col);
-//		self.slice_mut().get_unchecked_mut(index)
-//	}
-	
-	
-	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
-		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
-	}
-	
-	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
-		row * self.row_length_with_ln().get() + col
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get_row(&self, row: usize) -> Option<&[T]> {
-		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.as_slice().get(row_start..row_end)
-	}
-	
-//	fn rows(&self) -> Rows<T> {
-//		Rows::over(self)
-//	}
-	
-	
-	pub fn row_count(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.total_length_with_ln().get() / self.row_length_with_ln()) }
-	}
-	
-	pub fn row_length(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.row_length_with_ln().get() - 1) }
-	}
-	
-	pub fn row_length_with_ln(&self) -> NonZero<usize> {
-		self.row_length_with_ln
-	}
-	
-	pub fn total_length_with_ln(&self) -> NonZero<usize> {
-		self.total_length_with_ln // unsafe { NonZero::new_unchecked(self.slice().len()) }
-	}
-}
+use std::{iter, num::NonZero};
+
+use crate::error::{Checker, ParseError, ParseErrorKind};
+use crate::grid::GridRefWithLn;
 
  // - Part One - //
 
@@ -158,8 +53,58 @@ const DIRECTIONS: [(isize, isize); 8] = [
 	(  1, -1 ), (  1,  0 ), (  1,  1 )
 ];
 
+// Compares whole chunks at once (building a bit mask of matches) so that this compiles down to SIMD comparisons.
+fn positions_of(row: &[u8], byte: u8) -> impl Iterator<Item = usize> + '_ {
+	let (chunks, rest) = row.as_chunks::<32>();
+	let masks = chunks.iter().map(move |chunk| enumerate(chunk).fold(0u32, |mask, (i, &b)| mask | ((b == byte) as u32) << i));
+	let in_chunks = enumerate(masks).flat_map(|(chunk, mut mask)| iter::from_fn(move || {
+		(mask != 0).then(|| {
+			let bit = mask.trailing_zeros() as usize;
+			mask &= mask - 1;
+			chunk * 32 + bit
+		})
+	}));
+	
+	let rest_start = chunks.len() * 32;
+	in_chunks.chain(enumerate(rest).filter(move |&(_, &b)| b == byte).map(move |(i, _)| rest_start + i))
+}
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Hit {
+	pub row: usize,
+	pub col: usize,
+	pub direction: (isize, isize)
+}
+
+// Every occurrence of 'word' in any of the eight directions. Single-letter words only get one hit per cell, going right.
+pub fn find_word(grid: GridRefWithLn<u8>, word: &[u8]) -> Vec<Hit> {
+	let Some((&first, rest)) = word.split_first() else {
+		return Vec::new();
+	};
+	let directions: &[(isize, isize)] = if rest.is_empty() { &[(0, 1)] } else { &DIRECTIONS };
+	
+	let mut hits = Vec::new();
+	for (row, contents) in enumerate(grid.rows()) {
+		for col in positions_of(contents, first) {
+			for &direction in directions {
+				if check_matching_from(grid, (row, col), direction, rest) {
+					hits.push(Hit { row, col, direction });
+				}
+			}
+		}
+	}
+	
+	hits
+}
+
 #[aoc(day4, part1)]
-pub fn part1(input: &str) -> u32 { // This -relies- used to rely on all lines, including the last, being '\n'-terminated. (still relies on no "\r\n")
+pub fn part1(input: &str) -> u32 {
+	let grid = unsafe { GridRefWithLn::from_text(input).unwrap_unchecked() };
+	find_word(grid, b"XMAS").len() as u32
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> u32 { // This -relies- used to rely on all lines, including the last, being '\n'-terminated. (still relies on no "\r\n")
 	// println!("{}", input.len());
 	// let grid = GridRefWithLn::from_slice(input.as_bytes(), NonZero::new(input.find('\n').unwrap()/* + 1 */).unwrap()).unwrap();
 	let grid = unsafe { GridRefWithLn::from_slice(input.as_bytes(), NonZero::new_unchecked(input.find('\n').unwrap_unchecked())).unwrap_unchecked() };
@@ -176,10 +121,23 @@ pub fn part1(input: &str) -> u32 { // This -relies- used to rely on all lines, i
 			}
 		}
 	}
-			
+	
 	count
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(4, input);
+	if !input.contains('\n') { // Both parts find the row length using the first '\n'.
+		return Err(checker.error_at_end(input, ParseErrorKind::Expected(b'\n')));
+	}
+	
+	checker.grid(input, |_| true).map(|_| ())
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
  // - Part Two - //
 
 #[allow(unused)]
@@ -215,11 +173,107 @@ unsafe fn check_mas_unchecked(search: GridRefWithLn<u8>, center: (usize, usize))
 	matches!(backslash, (b'M', b'S') | (b'S', b'M')) && matches!(slash, (b'M', b'S') | (b'S', b'M'))
 }
 
+// A rectangular pattern of letters, where 'None' matches anything.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Stencil {
+	rows: usize,
+	cols: usize,
+	cells: Vec<Option<u8>>
+}
+
+impl Stencil {
+	// Returns 'None' if there are no rows, or they're empty or differ in length.
+	pub fn new(rows: &[&str], wildcard: u8) -> Option<Self> {
+		let cols = rows.first()?.len();
+		if cols == 0 || rows.iter().any(|row| row.len() != cols) {
+			return None;
+		}
+		
+		let cells = rows.iter().flat_map(|row| row.bytes()).map(|b| (b != wildcard).then_some(b)).collect();
+		Some(Self { rows: rows.len(), cols, cells })
+	}
+	
+	// Two "MAS"es crossing diagonally at the 'A'.
+	pub fn x_mas() -> Self {
+		unsafe { Self::new(&["M.S", ".A.", "M.S"], b'.').unwrap_unchecked() }
+	}
+	
+	pub fn rows(&self) -> usize {
+		self.rows
+	}
+	
+	pub fn cols(&self) -> usize {
+		self.cols
+	}
+	
+	pub fn get(&self, row: usize, col: usize) -> Option<u8> {
+		self.cells[row * self.cols + col]
+	}
+	
+	// A quarter turn clockwise.
+	pub fn rotated(&self) -> Self {
+		let cells = (0..self.cols).flat_map(|row| (0..self.rows).map(move |col| self.get(self.rows - 1 - col, row))).collect();
+		Self { rows: self.cols, cols: self.rows, cells }
+	}
+	
+	// Every different way round the stencil can be (so between one and four of them).
+	pub fn rotations(&self) -> Vec<Self> {
+		let mut rotations = vec![self.clone()];
+		for _ in 0..3 {
+			let next = unsafe { rotations.last().unwrap_unchecked() }.rotated();
+			if !rotations.contains(&next) {
+				rotations.push(next);
+			}
+		}
+		rotations
+	}
+	
+	fn matches_at(&self, grid: GridRefWithLn<u8>, top: usize, left: usize) -> bool {
+		(0..self.rows).all(|row| {
+			let contents = unsafe { grid.get_row(top + row).unwrap_unchecked() };
+			iter::zip(&contents[left..left + self.cols], &self.cells[row * self.cols..][..self.cols]).all(|(&b, &cell)| cell.is_none_or(|cell| cell == b))
+		})
+	}
+}
+
+// The top left corner of everywhere 'stencil' matches (as it is, not rotated).
+pub fn find_stencil(grid: GridRefWithLn<u8>, stencil: &Stencil) -> Vec<(usize, usize)> {
+	let (rows, cols) = (grid.row_count().get(), grid.row_length().get());
+	if stencil.rows > rows || stencil.cols > cols {
+		return Vec::new();
+	}
+	
+	let mut found = Vec::new();
+	let Some(anchor) = stencil.cells.iter().position(Option::is_some) else {
+		found.extend((0..=rows - stencil.rows).flat_map(|top| (0..=cols - stencil.cols).map(move |left| (top, left))));
+		return found;
+	};
+	
+	// Only look closer where the first letter of the stencil is.
+	let (anchor_row, anchor_col, letter) = (anchor / stencil.cols, anchor % stencil.cols, unsafe { stencil.cells[anchor].unwrap_unchecked() });
+	for top in 0..=rows - stencil.rows {
+		let contents = unsafe { grid.get_row(top + anchor_row).unwrap_unchecked() };
+		for left in positions_of(contents, letter).filter_map(|col| col.checked_sub(anchor_col)).filter(|&left| left + stencil.cols <= cols) {
+			if stencil.matches_at(grid, top, left) {
+				found.push((top, left));
+			}
+		}
+	}
+	
+	found
+}
+
 #[aoc(day4, part2)]
 pub fn part2(input: &str) -> u32 {
+	let grid = unsafe { GridRefWithLn::from_text(input).unwrap_unchecked() };
+	Stencil::x_mas().rotations().iter().map(|stencil| find_stencil(grid, stencil).len() as u32).sum()
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u32 {
 	// let grid = GridRefWithLn::from_slice(input.as_bytes(), NonZero::new(input.find('\n').unwrap()/* + 1 */).unwrap()).unwrap();
 	let grid = unsafe { GridRefWithLn::from_slice(input.as_bytes(), NonZero::new_unchecked(input.find('\n').unwrap_unchecked())).unwrap_unchecked() };
-
+	
 	let mut count = 0;
 	for row in 1..(grid.row_count().get() - 1) {
 		for col in 1..(grid.row_length().get() - 1 /* 2 */) {
@@ -229,3 +283,11 @@ pub fn part2(input: &str) -> u32 {
 	
 	count
 }
+
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
+fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
+	i.into_iter().enumerate()
+}
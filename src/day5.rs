@@ -1,7 +1,151 @@
-use std::{collections::{HashMap, HashSet}, hint, iter, mem, num};
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap, HashSet}, fmt, hash::Hash, hint, iter, mem, num, str::FromStr};
+
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
+// Pages that would each have to come before the next, and the last before the first.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Cycle<P>(pub Vec<P>);
+
+impl<P: fmt::Display> fmt::Display for Cycle<P> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "contradictory rules: ")?;
+		for page in &self.0 {
+			write!(f, "{page} before ")?;
+		}
+		match self.0.first() {
+			Some(first) => write!(f, "{first}"),
+			None => Ok(())
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct OrderingRules<P> {
+	// The pages each page has to come before.
+	after: HashMap<P, HashSet<P>>
+}
+
+impl<P> Default for OrderingRules<P> {
+	fn default() -> Self {
+		Self { after: HashMap::new() }
+	}
+}
+
+impl<P: Copy + Eq + Hash> OrderingRules<P> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	
+	// A rule like "5|5" says nothing about where a page goes, so it's ignored.
+	pub fn add(&mut self, before: P, after: P) {
+		if before != after {
+			self.after.entry(before).or_default().insert(after);
+		}
+	}
+	
+	pub fn must_precede(&self, before: P, after: P) -> bool {
+		self.after.get(&before).is_some_and(|pages| pages.contains(&after))
+	}
+	
+	// The positions of the first pair of pages (by where the later one is) that are the wrong way round.
+	pub fn first_violation(&self, update: &[P]) -> Option<(usize, usize)> {
+		enumerate(update).find_map(|(j, &page)| {
+			take(update, j).position(|&earlier| self.must_precede(page, earlier)).map(|i| (i, j))
+		})
+	}
+	
+	pub fn is_ordered(&self, update: &[P]) -> bool {
+		self.first_violation(update).is_none()
+	}
+	
+	// Sorts the pages in 'update' so that every rule between them holds, only moving pages that have to move relative to each other.
+	pub fn corrected(&self, update: &[P]) -> Result<Vec<P>, Cycle<P>> {
+		// Rules between pages outside of the update don't matter, so this works with positions within it.
+		let mut later: Vec<Vec<usize>> = vec![Vec::new(); update.len()];
+		let mut earlier_count = vec![0usize; update.len()];
+		for (i, &before) in enumerate(update) {
+			for (j, &after) in enumerate(update) {
+				if self.must_precede(before, after) {
+					later[i].push(j);
+					earlier_count[j] += 1;
+				}
+			}
+		}
+		
+		// Always taking the earliest available page keeps pages that are already in order where they are.
+		let mut ready: BinaryHeap<_> = filter(0..update.len(), |&i| earlier_count[i] == 0).map(Reverse).collect();
+		let mut order = Vec::with_capacity(update.len());
+		while let Some(Reverse(i)) = ready.pop() {
+			order.push(update[i]);
+			for &j in &later[i] {
+				earlier_count[j] -= 1;
+				if earlier_count[j] == 0 {
+					ready.push(Reverse(j));
+				}
+			}
+		}
+		
+		if order.len() == update.len() {
+			return Ok(order);
+		}
+		
+		// Every page left over has another left-over page that has to come before it, so following those back must go round in a cycle.
+		let mut seen_at = vec![None; update.len()];
+		let mut path = Vec::new();
+		let mut at = unsafe { (0..update.len()).find(|&i| earlier_count[i] > 0).unwrap_unchecked() };
+		while seen_at[at].is_none() {
+			seen_at[at] = Some(path.len());
+			path.push(at);
+			at = unsafe { (0..update.len()).find(|&i| earlier_count[i] > 0 && later[i].contains(&at)).unwrap_unchecked() };
+		}
+		
+		let cycle = &path[unsafe { seen_at[at].unwrap_unchecked() }..];
+		Err(Cycle(cycle.iter().rev().map(|&i| update[i]).collect()))
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct PrintQueue<P> {
+	pub rules: OrderingRules<P>,
+	pub updates: Vec<Vec<P>>
+}
+
+impl<P: Copy + Eq + Hash + FromStr> PrintQueue<P> {
+	pub fn parse(input: &str) -> Option<Self> {
+		let mut lines = input.lines();
+		
+		let mut rules = OrderingRules::new();
+		while let Some(rule) = lines.next().filter(|l| !l.is_empty()) {
+			let (before, after) = rule.split_once('|')?;
+			rules.add(before.parse().ok()?, after.parse().ok()?);
+		}
+		
+		let mut updates = Vec::with_capacity(200);
+		for line in lines {
+			updates.push(line.split(',').map(|num| num.parse().ok()).collect::<Option<_>>()?);
+		}
+		
+		Some(Self { rules, updates })
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check::<P>(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+}
+
+fn middle<P: Copy>(update: &[P]) -> P {
+	update[update.len() / 2]
+}
 
 #[aoc(day5, part1)]
 pub fn part1(input: &str) -> u32 {
+	let queue = unsafe { PrintQueue::<u32>::parse(input).unwrap_unchecked() };
+	filter(&queue.updates, |update| queue.rules.is_ordered(update)).map(|update| middle(update)).sum()
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> u32 {
 	let mut lines = input.lines();
 	
 	let mut ordering_rules: HashMap<u8, HashSet<u8>> = HashMap::new();
@@ -51,8 +195,38 @@ pub fn part1(input: &str) -> u32 {
 	}).sum()
 }
 
+fn check<P: FromStr>(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(5, input);
+	let mut lines = input.lines();
+	
+	while let Some(rule) = lines.next().filter(|l| !l.is_empty()) {
+		let (before, after) = checker.split_once(rule, "|")?;
+		checker.number::<P>(before)?;
+		checker.number::<P>(after)?;
+	}
+	
+	for line in lines {
+		for num in line.split(',') {
+			checker.number::<P>(num)?;
+		}
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	check::<u32>(input).map(|()| part1(input))
+}
+
 #[aoc(day5, part2)]
 pub fn part2(input: &str) -> u32 {
+	let queue = unsafe { PrintQueue::<u32>::parse(input).unwrap_unchecked() };
+	let unordered = filter(&queue.updates, |update| !queue.rules.is_ordered(update));
+	unordered.filter_map(|update| queue.rules.corrected(update).ok()).map(|update| middle(&update)).sum()
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u32 {
 	let mut lines = input.lines();
 	
 	let mut ordering_rules: HashMap<u8, HashSet<u8>> = HashMap::new();
@@ -124,6 +298,21 @@ pub fn part2(input: &str) -> u32 {
 	}).sum()
 }
 
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+	let queue = PrintQueue::<u32>::try_parse(input)?;
+	let checker = Checker::new(5, input);
+	
+	let mut total = 0;
+	for (update, line) in iter::zip(&queue.updates, input.lines().skip_while(|l| !l.is_empty()).skip(1)) {
+		if !queue.rules.is_ordered(update) {
+			let corrected = queue.rules.corrected(update).map_err(|_| checker.error_at(line, ParseErrorKind::Unsolvable))?;
+			total += middle(&corrected);
+		}
+	}
+	
+	Ok(total)
+}
+
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
@@ -131,3 +320,40 @@ fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 fn filter<I: IntoIterator, P: FnMut(&I::Item) -> bool>(i: I, f: P) -> iter::Filter<I::IntoIter, P> {
 	i.into_iter().filter(f)
 }
+
+fn take<I: IntoIterator>(i: I, n: usize) -> iter::Take<I::IntoIter> {
+	i.into_iter().take(n)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "47|53\n97|13\n97|61\n97|47\n75|29\n61|13\n75|53\n29|13\n97|29\n53|29\n61|53\n97|53\n61|29\n47|13\n75|47\n97|75\n47|61\n75|61\n47|29\n75|13\n53|13\n\n75,47,61,53,29\n97,61,53,29,13\n75,29,13\n75,97,47,61,53\n61,13,29\n97,13,75,29,47\n";
+	
+	#[test]
+	fn example() {
+		let queue = PrintQueue::<u32>::parse(EXAMPLE).unwrap();
+		let ordered: Vec<bool> = queue.updates.iter().map(|update| queue.rules.is_ordered(update)).collect();
+		assert_eq!(ordered, [true, true, true, false, false, false]);
+		assert_eq!(queue.rules.corrected(&queue.updates[3]), Ok(vec![97, 75, 47, 61, 53]));
+		assert_eq!(queue.rules.corrected(&queue.updates[4]), Ok(vec![61, 29, 13]));
+		assert_eq!(queue.rules.corrected(&queue.updates[5]), Ok(vec![97, 75, 47, 29, 13]));
+		assert_eq!((try_part1(EXAMPLE), try_part2(EXAMPLE)), (Ok(143), Ok(123)));
+	}
+	
+	#[test]
+	fn contradictory_rules() {
+		// Page 4 isn't part of the cycle, so shouldn't show up in it.
+		for input in ["1|2\n2|3\n3|1\n\n1,2,3\n", "1|2\n2|3\n3|1\n4|1\n\n4,3,2,1\n"] {
+			let queue = PrintQueue::<u32>::parse(input).unwrap();
+			let Err(Cycle(cycle)) = queue.rules.corrected(&queue.updates[0]) else { panic!("the rules can't all hold") };
+			assert_eq!(cycle.len(), 3, "{cycle:?}");
+			for (i, &page) in enumerate(&cycle) {
+				assert!(queue.rules.must_precede(page, cycle[(i + 1) % cycle.len()]), "{cycle:?}");
+			}
+			
+			assert_eq!(try_part2(input).unwrap_err().kind, ParseErrorKind::Unsolvable);
+		}
+	}
+}
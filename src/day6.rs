@@ -1,6 +1,9 @@
 // #![feature(ascii_char, new_zeroed_alloc)]
 use std::{ascii, /* collections::HashSet, */ mem, num::NonZero};
-use rustc_hash::{FxBuildHasher, FxHashSet as HashSet};
+use rustc_hash::{FxBuildHasher, FxHashMap as HashMap, FxHashSet as HashSet};
+
+use crate::error::{Checker, ParseError, ParseErrorKind};
+use crate::grid::Grid;
 
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum Space {
@@ -9,8 +12,8 @@ enum Space {
 	Visited
 }
 
-#[derive(Copy, Eq, Hash, Clone, PartialEq)]
-enum Direction {
+#[derive(Copy, Eq, Hash, Clone, PartialEq, Debug)]
+pub enum Direction {
 	North,
 	East,
 	South,
@@ -32,117 +35,6 @@ impl Direction {
 	}
 }
 
-#[derive(Clone)]
-struct Grid<T> {
-	grid: Vec<T>,
-	row_length: NonZero<usize>
-}
-
-impl<T> Grid<T> {
-	#[allow(unused)]
-	pub fn from_vec(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
-		assert_eq!(grid.len() % row_length, 0);
-		assert!( !grid.is_empty() );
-		
-		unsafe { Self::from_vec_unchecked(grid, row_length) }
-	}
-	
-	#[allow(unused)]
-	pub fn from_first_row(grid: Vec<T>) -> Result<Self, Vec<T>> {
-		if let Some(row_length) = NonZero::new(grid.len()) {
-			Ok(unsafe { Self::from_vec_unchecked(grid, row_length) })
-		} else {
-			Err(grid)
-		}
-	}
-	
-	pub unsafe fn from_vec_unchecked(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
-		Self { grid, row_length }
-	}
-	
-	pub unsafe fn as_vec_mut(&mut self) -> &mut Vec<T> {
-		&mut self.grid
-	}
-	
-	#[allow(unused)]
-	pub fn reserve_rows(&mut self, rows: usize) {
-		let capacity = self.row_length().get() * rows;
-		unsafe {
-			self.as_vec_mut().reserve(capacity);
-		}
-	}
-	
-	#[allow(unused)]
-	pub fn add_filled_row(&mut self, value: T) -> &mut [T] where T: Clone {
-		let prev_len = self.total_length().get();
-		let row_len = self.row_length().get();
-		unsafe {
-			self.as_vec_mut().resize(prev_len + row_len, value);
-			self.slice_mut().get_unchecked_mut(prev_len..)
-		}
-	}
-	
-	
-	pub fn slice(&self) -> &[T] {
-		&self.grid
-	}
-	
-	pub fn slice_mut(&mut self) -> &mut [T] {
-		&mut self.grid
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get(&self, row: usize, col: usize) -> Option<&T> {
-		self.to_index(row, col).map(|i| unsafe { self.slice().get_unchecked(i) })
-	}
-	
-	pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
-		self.to_index(row, col).map(|i| unsafe { self.slice_mut().get_unchecked_mut(i) })
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &T {
-		self.slice().get_unchecked(self.to_index_unchecked(row, col))
-	}
-	
-	#[allow(unused)]
-	pub unsafe fn get_unchecked_mut(&mut self, row: usize, col: usize) -> &mut T {
-		let index = self.to_index_unchecked(row, col);
-		self.slice_mut().get_unchecked_mut(index)
-	}
-	
-	
-	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
-		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
-	}
-	
-	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
-		row * self.row_length().get() + col
-	}
-	
-	
-	#[allow(unused)]
-	pub fn get_row(&self, row: usize) -> Option<&[T]> {
-		let row_start = row.checked_mul(self.row_length().get())?;
-		let row_end = row_start + self.row_length().get();
-		self.slice().get(row_start..row_end)
-	}
-	
-	
-	pub fn row_count(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.total_length().get() / self.row_length()) }
-	}
-	
-	pub fn row_length(&self) -> NonZero<usize> {
-		self.row_length
-	}
-	
-	pub fn total_length(&self) -> NonZero<usize> {
-		unsafe { NonZero::new_unchecked(self.slice().len()) }
-	}
-}
-
 fn parse_to_grid(s: &[ascii::Char]) -> Option<(Grid<Space>, Option<(usize, usize)>)> {
 	let row_length = NonZero::new(s.as_str().find('\n').unwrap_or(s.len()))?; // s.as_str().find('\n').and_then(NonZero::new)?;
 	let row_length_with_ln = row_length.checked_add(1)?;
@@ -167,7 +59,7 @@ fn parse_to_grid(s: &[ascii::Char]) -> Option<(Grid<Space>, Option<(usize, usize
 				start = Some((row, col));
 			}
 		}
-			
+		
 		for (col, _) in contents.match_indices('#') {
 			*unsafe { grid.get_unchecked_mut(row, col) } = Space::Wall;
 		}
@@ -211,10 +103,232 @@ pub fn part1(input: &str) -> u32 {
 			None => break
 		}
 	}
-			
+	
 	visited
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(6, input);
+	checker.grid(input, |b| matches!(b, b'.' | b'#' | b'^'))?;
+	checker.unique(input, b'^').map(|_| ())
+}
+
+// 'part1' would walk forever if the guard never leaves.
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+	match Patrol::try_parse(input)?.outcome() {
+		Outcome::Leaves{ .. } => Ok(part1(input)),
+		Outcome::Loops(_) => Err(Checker::new(6, input).error_at_end(input, ParseErrorKind::Unsolvable))
+	}
+}
+
+type State = ((usize, usize), Direction);
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum Outcome {
+	// Where the guard was (and which way they were facing) just before walking off the map, and how many steps that took.
+	Leaves{ position: (usize, usize), facing: Direction, steps: usize },
+	Loops(LoopClosure)
+}
+
+// The first turn the guard makes for a second time, and how many steps it took them to get back to it.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct LoopClosure {
+	pub position: (usize, usize),
+	pub facing: Direction,
+	pub length: usize
+}
+
+#[derive(Clone)]
+pub struct Patrol {
+	grid: Grid<Space>,
+	start: (usize, usize)
+}
+
+impl Patrol {
+	pub fn parse(input: &str) -> Option<Self> {
+		let (grid, start) = parse_to_grid(input.as_bytes().as_ascii()?)?;
+		Some(Self { grid, start: start? })
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+	
+	pub fn start(&self) -> (usize, usize) {
+		self.start
+	}
+	
+	// Returns 'None' once the guard leaves the map.
+	fn advance(&self, (position, facing): State, obstruction: Option<(usize, usize)>) -> Option<State> {
+		let to = wrapping_offset(position, facing);
+		match self.grid.get(to.0, to.1)? {
+			_ if obstruction == Some(to) => Some((position, facing.turned_right())),
+			Space::Wall => Some((position, facing.turned_right())),
+			_ => Some((to, facing))
+		}
+	}
+	
+	// Every position and direction the guard is in, turns included, up until they leave or are about to start going round a loop again.
+	pub fn path(&self) -> Steps<'_> {
+		self.path_with_obstruction(None)
+	}
+	
+	pub fn path_with_obstruction(&self, obstruction: Option<(usize, usize)>) -> Steps<'_> {
+		Steps {
+			patrol: self,
+			obstruction,
+			current: Some(((self.start, Direction::North), 0)),
+			turns: HashMap::with_capacity_and_hasher(256, FxBuildHasher),
+			outcome: None
+		}
+	}
+	
+	pub fn outcome(&self) -> Outcome {
+		self.outcome_with_obstruction(None)
+	}
+	
+	pub fn outcome_with_obstruction(&self, obstruction: Option<(usize, usize)>) -> Outcome {
+		let mut steps = self.path_with_obstruction(obstruction);
+		steps.by_ref().for_each(drop);
+		unsafe { steps.outcome().unwrap_unchecked() }
+	}
+	
+	// Same as 'outcome_with_obstruction', but starting part way along the path and reusing 'turns'.
+	fn outcome_from(&self, from: State, from_step: usize, obstruction: (usize, usize), turns: &mut HashMap<State, usize>) -> Outcome {
+		turns.clear();
+		let (mut state, mut step) = (from, from_step);
+		loop {
+			let Some(next) = self.advance(state, Some(obstruction)) else {
+				return Outcome::Leaves{ position: state.0, facing: state.1, steps: step };
+			};
+			
+			if next.0 == state.0 {
+				if let Some(first) = turns.insert(state, step) {
+					return Outcome::Loops(LoopClosure { position: state.0, facing: state.1, length: step - first });
+				}
+			}
+			
+			state = next;
+			step += 1;
+		}
+	}
+	
+	// Each position the guard walks onto, along with where they were just before first doing so (and at which step).
+	fn obstruction_candidates(&self) -> Vec<(State, usize, (usize, usize))> {
+		let mut seen = vec![false; self.grid.total_length().get()];
+		seen[self.grid.to_index_unchecked(self.start.0, self.start.1)] = true;
+		
+		let mut candidates = Vec::with_capacity(self.grid.total_length().get() / 2);
+		let mut previous = None;
+		for (step, state @ ((row, col), _)) in self.path().enumerate() {
+			// The guard would have run into the obstruction the first time they tried to walk onto it, so that's where to start from.
+			if let Some(previous) = previous.filter(|_| !mem::replace(&mut seen[self.grid.to_index_unchecked(row, col)], true)) {
+				candidates.push((previous, step - 1, (row, col)));
+			}
+			
+			previous = Some(state);
+		}
+		
+		candidates
+	}
+	
+	// Every position where adding an obstruction would trap the guard in a loop, in the order the guard first reaches them.
+	#[cfg(not(feature = "parallel"))]
+	pub fn loop_obstructions(&self) -> Vec<(usize, usize)> {
+		let mut turns = HashMap::with_capacity_and_hasher(256, FxBuildHasher);
+		let loops = |&(from, from_step, at): &_| matches!(self.outcome_from(from, from_step, at, &mut turns), Outcome::Loops(_)).then_some(at);
+		self.obstruction_candidates().iter().filter_map(loops).collect()
+	}
+	
+	// Every position where adding an obstruction would trap the guard in a loop, in the order the guard first reaches them.
+	#[cfg(feature = "parallel")]
+	pub fn loop_obstructions(&self) -> Vec<(usize, usize)> {
+		use rayon::prelude::*;
+		
+		self.obstruction_candidates().par_iter().map_init(
+			|| HashMap::with_capacity_and_hasher(256, FxBuildHasher),
+			|turns, &(from, from_step, at)| matches!(self.outcome_from(from, from_step, at, turns), Outcome::Loops(_)).then_some(at)
+		).flatten().collect()
+	}
+	
+	// Draws the path like the puzzle does, marking 'obstructions' with 'O'.
+	pub fn render(&self, obstruction: Option<(usize, usize)>, obstructions: &[(usize, usize)]) -> String {
+		let mut marks = Grid::from_vec(vec![b'.'; self.grid.total_length().get()], self.grid.row_length());
+		for (mark, &space) in marks.as_mut_slice().iter_mut().zip(self.grid.as_slice()) {
+			if space == Space::Wall {
+				*mark = b'#';
+			}
+		}
+		
+		let mut previous: Option<State> = None;
+		for state @ ((row, col), facing) in self.path_with_obstruction(obstruction) {
+			let line = if matches!(facing, Direction::North | Direction::South) { b'|' } else { b'-' };
+			let mark = unsafe { marks.get_unchecked_mut(row, col) };
+			*mark = match *mark {
+				b'.' => line,
+				b'+' => b'+',
+				_ if previous.is_some_and(|(at, _)| at == (row, col)) || *mark != line => b'+',
+				_ => line
+			};
+			previous = Some(state);
+		}
+		
+		*unsafe { marks.get_unchecked_mut(self.start.0, self.start.1) } = b'^';
+		for &(row, col) in obstruction.iter().chain(obstructions) {
+			if let Some(mark) = marks.get_mut(row, col) {
+				*mark = b'O';
+			}
+		}
+		
+		let mut rendered = String::with_capacity(marks.total_length().get() + marks.row_count().get());
+		for row in marks.rows() {
+			rendered.extend(row.iter().map(|&b| b as char));
+			rendered.push('\n');
+		}
+		rendered
+	}
+}
+
+pub struct Steps<'a> {
+	patrol: &'a Patrol,
+	obstruction: Option<(usize, usize)>,
+	current: Option<(State, usize)>,
+	// The step at which the guard turned in each position and direction.
+	turns: HashMap<State, usize>,
+	outcome: Option<Outcome>
+}
+
+impl Steps<'_> {
+	// Only known once the iterator is exhausted.
+	pub fn outcome(&self) -> Option<Outcome> {
+		self.outcome
+	}
+}
+
+impl Iterator for Steps<'_> {
+	type Item = ((usize, usize), Direction);
+	
+	fn next(&mut self) -> Option<Self::Item> {
+		let (state @ (position, facing), step) = self.current.take()?;
+		match self.patrol.advance(state, self.obstruction) {
+			None => self.outcome = Some(Outcome::Leaves{ position, facing, steps: step }),
+			Some(next) => {
+				if next.0 == position {
+					if let Some(first) = self.turns.insert(state, step) {
+						self.outcome = Some(Outcome::Loops(LoopClosure { position, facing, length: step - first }));
+						return None;
+					}
+				}
+				
+				self.current = Some((next, step + 1));
+			}
+		}
+		
+		Some(state)
+	}
+}
+
 fn check_loop_with_wall(grid: &Grid<Space>, wall_at: (usize, usize), from: (usize, usize), from_dir: Direction,
 		outer_wall_hits: &HashSet<(usize, usize)>, inner_wall_hits_buffer: &mut HashSet<(usize, usize)>) -> bool {
 	inner_wall_hits_buffer.clear();
@@ -251,6 +365,12 @@ fn check_loop_with_wall(grid: &Grid<Space>, wall_at: (usize, usize), from: (usiz
 
 #[aoc(day6, part2)]
 pub fn part2(input: &str) -> u32 {
+	let patrol = unsafe { Patrol::parse(input).unwrap_unchecked() };
+	patrol.loop_obstructions().len() as u32
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u32 {
 	// let (mut grid, start) = parse_to_grid(input.as_ascii().unwrap()).unwrap();
 	let (mut grid, start) = unsafe { parse_to_grid(input.as_bytes().as_ascii_unchecked()).unwrap_unchecked() };
 	
@@ -283,6 +403,85 @@ pub fn part2(input: &str) -> u32 {
 			None => break
 		}
 	}
-			
+	
 	possibilities
 }
+
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "....#.....\n.........#\n..........\n..#.......\n.......#..\n..........\n.#..^.....\n........#.\n#.........\n......#...\n";
+	
+	#[test]
+	fn example() {
+		let patrol = Patrol::parse(EXAMPLE).unwrap();
+		assert_eq!(patrol.start(), (6, 4));
+		assert_eq!(patrol.path().map(|(position, _)| position).collect::<HashSet<_>>().len(), 41);
+		assert_eq!(patrol.outcome(), Outcome::Leaves{ position: (9, 7), facing: Direction::South, steps: 54 });
+		
+		let mut obstructions = patrol.loop_obstructions();
+		obstructions.sort_unstable();
+		assert_eq!(obstructions, [(6, 3), (7, 6), (7, 7), (8, 1), (8, 3), (9, 7)]);
+		assert_eq!((try_part1(EXAMPLE), try_part2(EXAMPLE)), (Ok(41), Ok(6)));
+	}
+	
+	#[test]
+	fn loops_without_obstruction() {
+		let input = ".#..\n...#\n#^..\n..#.\n";
+		let patrol = Patrol::parse(input).unwrap();
+		assert_eq!(patrol.outcome(), Outcome::Loops(LoopClosure { position: (1, 1), facing: Direction::North, length: 8 }));
+		assert_eq!(try_part1(input).unwrap_err().kind, ParseErrorKind::Unsolvable);
+	}
+	
+	// Tries every empty cell, listing the ones that trap the guard in the order the unobstructed guard first reaches them.
+	fn brute_force(input: &str) -> Vec<(usize, usize)> {
+		let patrol = Patrol::parse(input).unwrap();
+		let loops: HashSet<_> = input.lines().enumerate()
+			.flat_map(|(row, line)| line.bytes().enumerate().filter(|&(_, b)| b == b'.').map(move |(col, _)| (row, col)))
+			.filter(|&at| matches!(patrol.outcome_with_obstruction(Some(at)), Outcome::Loops(_)))
+			.collect();
+		
+		let mut reached = HashSet::default();
+		let ordered: Vec<_> = patrol.path().map(|(position, _)| position).filter(|&at| reached.insert(at) && loops.contains(&at)).collect();
+		assert_eq!(ordered.len(), loops.len(), "an obstruction off the path can't change it");
+		ordered
+	}
+	
+	#[test]
+	fn loop_obstructions_match_brute_force() {
+		for input in [EXAMPLE, "^\n", "#.\n^.\n", ".#..\n...#\n#^..\n....\n"] {
+			assert_eq!(Patrol::parse(input).unwrap().loop_obstructions(), brute_force(input), "{input}");
+		}
+		
+		let mut seed = 0x9e3779b97f4a7c15u64;
+		let mut random = |n: usize| {
+			seed ^= seed << 13;
+			seed ^= seed >> 7;
+			seed ^= seed << 17;
+			seed as usize % n
+		};
+		
+		for _ in 0..500 {
+			let (rows, cols) = (1 + random(16), 1 + random(16));
+			let mut cells: Vec<u8> = (0..rows * cols).map(|_| if random(6) == 0 { b'#' } else { b'.' }).collect();
+			cells[random(rows * cols)] = b'^';
+			
+			let mut input = String::with_capacity((cols + 1) * rows);
+			for row in cells.chunks(cols) {
+				input.extend(row.iter().map(|&b| b as char));
+				input.push('\n');
+			}
+			
+			// If the guard already loops, every cell they never reach trivially keeps them looping.
+			let patrol = Patrol::parse(&input).unwrap();
+			if matches!(patrol.outcome(), Outcome::Leaves{ .. }) {
+				assert_eq!(patrol.loop_obstructions(), brute_force(&input), "\n{input}");
+			}
+		}
+	}
+}
@@ -1,8 +1,148 @@
-use std::num::NonZero;
+use std::{fmt::Write as _, num::NonZero};
+
+use crate::error::{Checker, ParseError, ParseErrorKind};
+
+// Equations are evaluated left to right, and solved by working backwards from the result, which needs each operator's inverse.
+pub trait Operator {
+	fn symbol(&self) -> &str;
+	
+	fn apply(&self, left: u128, right: u128) -> Option<u128>;
+	
+	// The 'left' that makes 'apply(left, right)' give 'result', if there is one.
+	fn invert(&self, result: u128, right: u128) -> Option<u128>;
+}
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Add;
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Mul;
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Concat;
+
+impl Operator for Add {
+	fn symbol(&self) -> &str { "+" }
+	
+	fn apply(&self, left: u128, right: u128) -> Option<u128> {
+		left.checked_add(right)
+	}
+	
+	fn invert(&self, result: u128, right: u128) -> Option<u128> {
+		result.checked_sub(right)
+	}
+}
+
+impl Operator for Mul {
+	fn symbol(&self) -> &str { "*" }
+	
+	fn apply(&self, left: u128, right: u128) -> Option<u128> {
+		left.checked_mul(right)
+	}
+	
+	// Multiplying by zero loses 'left' entirely, so that's never inverted.
+	fn invert(&self, result: u128, right: u128) -> Option<u128> {
+		(right != 0 && result.is_multiple_of(right)).then(|| result / right)
+	}
+}
+
+fn digit_multiplier(of: u128) -> Option<u128> {
+	10u128.checked_pow(of.checked_ilog10().unwrap_or(0) + 1)
+}
+
+impl Operator for Concat {
+	fn symbol(&self) -> &str { "||" }
+	
+	fn apply(&self, left: u128, right: u128) -> Option<u128> {
+		left.checked_mul(digit_multiplier(right)?)?.checked_add(right)
+	}
+	
+	fn invert(&self, result: u128, right: u128) -> Option<u128> {
+		let multiplier = digit_multiplier(right)?;
+		(result % multiplier == right).then(|| result / multiplier)
+	}
+}
+
+pub const PART1_OPERATORS: [&dyn Operator; 2] = [&Add, &Mul];
+pub const PART2_OPERATORS: [&dyn Operator; 3] = [&Add, &Mul, &Concat];
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Equation {
+	pub target: u128,
+	// Never empty, and never zero.
+	values: Vec<u128>
+}
+
+impl Equation {
+	// Zero values aren't allowed, since multiplying by zero can't be worked backwards from.
+	pub fn new(target: u128, values: Vec<u128>) -> Option<Self> {
+		(!values.is_empty() && !values.contains(&0)).then_some(Self { target, values })
+	}
+	
+	pub fn parse(line: &str) -> Option<Self> {
+		let (target, values) = line.split_once(':')?;
+		let values = values.split_whitespace().map(|value| value.parse().ok()).collect::<Option<_>>()?;
+		Self::new(target.parse().ok()?, values)
+	}
+	
+	pub fn parse_all(input: &str) -> Option<Vec<Self>> {
+		input.lines().map(Self::parse).collect()
+	}
+	
+	pub fn values(&self) -> &[u128] {
+		&self.values
+	}
+	
+	// The operators to put between the values to make the target, if there's any way to. Earlier operators in 'operators' are tried first.
+	pub fn solve<'o>(&self, operators: &[&'o dyn Operator]) -> Option<Vec<&'o dyn Operator>> {
+		let mut chosen = Vec::with_capacity(self.values.len() - 1);
+		let (&first, rest) = unsafe { self.values.split_first().unwrap_unchecked() };
+		self.solve_from_end(operators, first, rest, self.target, &mut chosen).then(|| {
+			chosen.reverse();
+			chosen
+		})
+	}
+	
+	pub fn is_solvable(&self, operators: &[&dyn Operator]) -> bool {
+		self.solve(operators).is_some()
+	}
+	
+	fn solve_from_end<'o>(&self, operators: &[&'o dyn Operator], first: u128, rest: &[u128], current: u128, chosen: &mut Vec<&'o dyn Operator>) -> bool {
+		let Some((&last, rest)) = rest.split_last() else {
+			return current == first;
+		};
+		
+		for &operator in operators {
+			if let Some(previous) = operator.invert(current, last) {
+				chosen.push(operator);
+				if self.solve_from_end(operators, first, rest, previous, chosen) {
+					return true;
+				}
+				chosen.pop();
+			}
+		}
+		
+		false
+	}
+	
+	// E.g. "3267 = 81 + 40 * 27". Assumes there's one operator for each gap between values.
+	pub fn expression(&self, operators: &[&dyn Operator]) -> String {
+		let (first, rest) = unsafe { self.values.split_first().unwrap_unchecked() };
+		let mut expression = format!("{} = {first}", self.target);
+		for (operator, value) in operators.iter().zip(rest) {
+			let _ = write!(expression, " {} {value}", operator.symbol());
+		}
+		expression
+	}
+}
+
+pub fn calibration_total(equations: &[Equation], operators: &[&dyn Operator]) -> u128 {
+	equations.iter().filter(|equation| equation.is_solvable(operators)).map(|equation| equation.target).sum()
+}
 
 fn parse_line<'b>(line: &str, buffer: &'b mut Vec<NonZero<u64>>) -> Option<(NonZero<u64>, NonZero<u64>, &'b [NonZero<u64>])> {
 	let (target, values) = line.split_once(':')?;
-		
+	
 	let target = target.parse().ok()?;
 	buffer.clear();
 	for value in values.split_whitespace().map(str::parse).map(Result::ok) {
@@ -22,7 +162,13 @@ fn check_for_match_from_end_1(target: NonZero<u64>, remaining: &[NonZero<u64>],
 }
 
 #[aoc(day7, part1)]
-pub fn part1(input: &str) -> u64 {
+pub fn part1(input: &str) -> u128 {
+	let equations = unsafe { Equation::parse_all(input).unwrap_unchecked() };
+	calibration_total(&equations, &PART1_OPERATORS)
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> u64 {
 	let mut buffer = Vec::with_capacity(12);
 	input.lines().filter_map(|line| {
 	//	let (target, values) = line.split_once(':').unwrap();
@@ -34,6 +180,28 @@ pub fn part1(input: &str) -> u64 {
 	}).sum()
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(7, input);
+	for line in input.lines() {
+		let (target, values) = checker.split_once(line, ":")?;
+		checker.number::<NonZero<u128>>(target)?;
+		
+		let mut values = values.split_whitespace().peekable();
+		if values.peek().is_none() {
+			return Err(checker.error_at_end(line, ParseErrorKind::UnexpectedEnd));
+		}
+		for value in values {
+			checker.number::<NonZero<u128>>(value)?;
+		}
+	}
+	
+	Ok(())
+}
+
+pub fn try_part1(input: &str) -> Result<u128, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 fn deconcatenate(from: u64, remove: NonZero<u64>) -> Option<u64> {
 	const TEN: NonZero<u64> = NonZero::new(10).unwrap();
 	
@@ -51,7 +219,13 @@ fn check_for_match_from_end_2(target: NonZero<u64>, remaining: &[NonZero<u64>],
 }
 
 #[aoc(day7, part2)]
-pub fn part2(input: &str) -> u64 {
+pub fn part2(input: &str) -> u128 {
+	let equations = unsafe { Equation::parse_all(input).unwrap_unchecked() };
+	calibration_total(&equations, &PART2_OPERATORS)
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u64 {
 	let mut buffer = Vec::with_capacity(12);
 	input.lines().filter_map(|line| {
 		let (start, target, remaining) = unsafe { parse_line(line, &mut buffer).unwrap_unchecked() };
@@ -59,6 +233,10 @@ pub fn part2(input: &str) -> u64 {
 	}).sum()
 }
 
+pub fn try_part2(input: &str) -> Result<u128, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn concatenate(left: NonZero<u64>, right: NonZero<u64>) -> Option<NonZero<u64>> {
 	const TEN: NonZero<u64> = NonZero::new(10).unwrap();
 	
@@ -75,4 +253,76 @@ fn check_for_match(target: NonZero<u64>, current: NonZero<u64>, remaining: &[Non
 				current.checked_mul(next).is_some_and(|product| check_for_match(target, product, remaining)) ||
 				concatenate(current, next).is_some_and(|result| check_for_match(target, result, remaining))
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "190: 10 19\n3267: 81 40 27\n83: 17 5\n156: 15 6\n7290: 6 8 6 15\n161011: 16 10 13\n192: 17 8 14\n21037: 9 7 18 13\n292: 11 6 16 20\n";
+	
+	fn symbols(operators: &[&dyn Operator]) -> Vec<String> {
+		operators.iter().map(|operator| operator.symbol().to_owned()).collect()
+	}
+	
+	// Applies 'operators' left to right, as the puzzle does.
+	fn evaluate(equation: &Equation, operators: &[&dyn Operator]) -> Option<u128> {
+		let (&first, rest) = equation.values().split_first()?;
+		operators.iter().zip(rest).try_fold(first, |left, (operator, &right)| operator.apply(left, right))
+	}
+	
+	#[test]
+	fn example() {
+		let equations = Equation::parse_all(EXAMPLE).unwrap();
+		
+		// Working backwards, the last gap's operators are tried first, so '+' is settled on there before '*' is needed in front of it.
+		let chosen = equations[1].solve(&PART1_OPERATORS).unwrap();
+		assert_eq!(symbols(&chosen), ["*", "+"]);
+		assert_eq!(equations[1].expression(&chosen), "3267 = 81 * 40 + 27");
+		
+		let chosen = equations[6].solve(&PART2_OPERATORS).unwrap();
+		assert_eq!(equations[6].expression(&chosen), "192 = 17 || 8 + 14");
+		assert!(equations[3].solve(&PART1_OPERATORS).is_none());
+		
+		for operators in [&PART1_OPERATORS[..], &PART2_OPERATORS] {
+			for equation in &equations {
+				if let Some(chosen) = equation.solve(operators) {
+					assert_eq!(chosen.len(), equation.values().len() - 1);
+					assert_eq!(evaluate(equation, &chosen), Some(equation.target), "{}", equation.expression(&chosen));
+				}
+			}
+		}
+		
+		assert_eq!((calibration_total(&equations, &PART1_OPERATORS), calibration_total(&equations, &PART2_OPERATORS)), (3749, 11387));
+		assert_eq!((try_part1(EXAMPLE), try_part2(EXAMPLE)), (Ok(3749), Ok(11387)));
+	}
+	
+	#[test]
+	fn user_defined_operator() {
+		struct Sub;
+		
+		impl Operator for Sub {
+			fn symbol(&self) -> &str { "-" }
+			
+			fn apply(&self, left: u128, right: u128) -> Option<u128> {
+				left.checked_sub(right)
+			}
+			
+			fn invert(&self, result: u128, right: u128) -> Option<u128> {
+				result.checked_add(right)
+			}
+		}
+		
+		let equation = Equation::parse("5: 10 3 2").unwrap();
+		assert!(equation.solve(&PART2_OPERATORS).is_none());
+		
+		let chosen = equation.solve(&[&Add, &Sub]).unwrap();
+		assert_eq!(equation.expression(&chosen), "5 = 10 - 3 - 2");
+		assert_eq!(evaluate(&equation, &chosen), Some(5));
+		
+		// Either way round works, so which one comes back depends on which operator is tried first in the last gap.
+		let equation = Equation::parse("10: 10 1 1").unwrap();
+		assert_eq!(equation.expression(&equation.solve(&[&Add, &Sub]).unwrap()), "10 = 10 - 1 + 1");
+		assert_eq!(equation.expression(&equation.solve(&[&Sub, &Add]).unwrap()), "10 = 10 + 1 - 1");
+		assert_eq!(calibration_total(&[equation], &[&Sub, &Mul]), 10);
+	}
+}
@@ -1,16 +1,152 @@
+use std::iter;
+
 use rustc_hash::{FxBuildHasher, FxHashMap as HashMap, FxHashSet as HashSet};
 
+use crate::error::{Checker, ParseError};
+
+// Which multiples of the distance between two antennas, going outwards from each of them, get antinodes. The antennas themselves are at 0.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Harmonics {
+	pub min: u32,
+	// 'None' for as far as the map goes.
+	pub max: Option<u32>
+}
+
+impl Harmonics {
+	pub const PART1: Self = Self { min: 1, max: Some(1) };
+	pub const PART2: Self = Self { min: 0, max: None };
+}
+
+#[derive(Clone, Debug)]
+pub struct AntennaMap {
+	rows: usize,
+	cols: usize,
+	// Sorted by frequency.
+	antennas: Vec<(u8, Vec<(usize, usize)>)>
+}
+
+impl AntennaMap {
+	// Returns 'None' if the rows differ in length, or there aren't any.
+	pub fn parse(input: &str) -> Option<Self> {
+		let cols = input.lines().next().filter(|first| !first.is_empty())?.len();
+		let mut by_frequency: HashMap<u8, Vec<(usize, usize)>> = HashMap::with_capacity_and_hasher(64, FxBuildHasher);
+		
+		let mut rows = 0;
+		for (row, line) in enumerate(input.lines()) {
+			if line.len() != cols {
+				return None;
+			}
+			for (col, frequency) in enumerate(line.bytes()).filter(|&(_, b)| b != b'.') {
+				by_frequency.entry(frequency).or_default().push((row, col));
+			}
+			rows += 1;
+		}
+		
+		let mut antennas: Vec<_> = by_frequency.into_iter().collect();
+		antennas.sort_unstable_by_key(|&(frequency, _)| frequency);
+		Some(Self { rows, cols, antennas })
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+	
+	pub fn rows(&self) -> usize {
+		self.rows
+	}
+	
+	pub fn cols(&self) -> usize {
+		self.cols
+	}
+	
+	pub fn frequencies(&self) -> impl Iterator<Item = u8> + '_ {
+		self.antennas.iter().map(|&(frequency, _)| frequency)
+	}
+	
+	pub fn antennas(&self, frequency: u8) -> &[(usize, usize)] {
+		self.antennas.binary_search_by_key(&frequency, |&(f, _)| f).map_or(&[], |i| &self.antennas[i].1)
+	}
+	
+	fn offset_within(&self, (row, col): (usize, usize), (row_delta, col_delta): (i64, i64), times: u32) -> Option<(usize, usize)> {
+		let row = row as i64 + row_delta * times as i64;
+		let col = col as i64 + col_delta * times as i64;
+		((0..self.rows as i64).contains(&row) && (0..self.cols as i64).contains(&col)).then_some((row as usize, col as usize))
+	}
+	
+	fn antinodes_of(&self, positions: &[(usize, usize)], harmonics: Harmonics, antinodes: &mut Vec<(usize, usize)>) {
+		for (i, &a) in enumerate(positions) {
+			for &b in &positions[i + 1..] {
+				let delta = (b.0 as i64 - a.0 as i64, b.1 as i64 - a.1 as i64);
+				for (from, delta) in [(a, (-delta.0, -delta.1)), (b, delta)] {
+					// Once one harmonic is off the map, so is every one after it.
+					let harmonics = (harmonics.min..).take_while(|&k| harmonics.max.is_none_or(|max| k <= max));
+					antinodes.extend(harmonics.map_while(|k| self.offset_within(from, delta, k)));
+				}
+			}
+		}
+	}
+	
+	// Each frequency's antinodes, in reading order. Different frequencies can share the same antinode.
+	pub fn antinodes_by_frequency(&self, harmonics: Harmonics) -> Vec<(u8, Vec<(usize, usize)>)> {
+		map(&self.antennas, |(frequency, positions)| {
+			let mut antinodes = Vec::new();
+			self.antinodes_of(positions, harmonics, &mut antinodes);
+			antinodes.sort_unstable();
+			antinodes.dedup();
+			(*frequency, antinodes)
+		}).collect()
+	}
+	
+	// Every antinode of any frequency, in reading order.
+	pub fn antinodes(&self, harmonics: Harmonics) -> Vec<(usize, usize)> {
+		let mut antinodes = Vec::with_capacity(1500);
+		for (_, positions) in &self.antennas {
+			self.antinodes_of(positions, harmonics, &mut antinodes);
+		}
+		antinodes.sort_unstable();
+		antinodes.dedup();
+		antinodes
+	}
+	
+	// The map with a '#' for each of 'antinodes' that isn't on top of an antenna.
+	pub fn render(&self, antinodes: &[(usize, usize)]) -> String {
+		let mut tiles = vec![b'.'; self.rows * self.cols];
+		for &(row, col) in antinodes {
+			tiles[row * self.cols + col] = b'#';
+		}
+		for (frequency, positions) in &self.antennas {
+			for &(row, col) in positions {
+				tiles[row * self.cols + col] = *frequency;
+			}
+		}
+		
+		let mut rendered = String::with_capacity(tiles.len() + self.rows);
+		for row in tiles.chunks_exact(self.cols) {
+			rendered.extend(row.iter().map(|&b| b as char));
+			rendered.push('\n');
+		}
+		rendered
+	}
+}
+
 fn try_offset_within(start: (u16, u16), offset: (i32, i32), max: (u16, u16)) -> Option<(u16, u16)> {
 	Some((
 		(start.0 as u32).checked_add_signed(offset.0).and_then(|r| r.try_into().ok()).filter(|&row| row <= max.0)?,
-		(start.1 as u32).checked_add_signed(offset.1).and_then(|c| c.try_into().ok()).filter(|&col| col <= max.0)?
+		(start.1 as u32).checked_add_signed(offset.1).and_then(|c| c.try_into().ok()).filter(|&col| col <= max.1)?
 	))
 }
 
 #[aoc(day8, part1)]
 pub fn part1(input: &str) -> usize {
+	let map = unsafe { AntennaMap::parse(input).unwrap_unchecked() };
+	map.antinodes(Harmonics::PART1).len()
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> usize {
 	let mut antennae = HashMap::with_capacity_and_hasher(36, FxBuildHasher);
-	let (mut max_row, max_col) = (0u16, input.lines().next().unwrap_or_default().len() as u16);
+	let (mut max_row, max_col) = (0u16, input.lines().next().unwrap_or_default().len().saturating_sub(1) as u16);
 	
 	for (row, line) in input.lines().enumerate().map(|(r, l)| (r as u16, l)) {
 		max_row = row;
@@ -22,7 +158,7 @@ pub fn part1(input: &str) -> usize {
 	let bounds = (max_row, max_col);
 	
 	let mut antinodes = HashSet::with_capacity_and_hasher(400, FxBuildHasher);
-
+	
 	for (_, positions) in antennae {
 		let mut positions = positions.iter();
 		while let Some(&a) = positions.next() {
@@ -39,10 +175,24 @@ pub fn part1(input: &str) -> usize {
 	antinodes.len()
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	Checker::new(8, input).grid(input, |b| b.is_ascii_alphanumeric() || b == b'.').map(|_| ())
+}
+
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 #[aoc(day8, part2)]
 pub fn part2(input: &str) -> usize {
+	let map = unsafe { AntennaMap::parse(input).unwrap_unchecked() };
+	map.antinodes(Harmonics::PART2).len()
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> usize {
 	let mut antennae = HashMap::with_capacity_and_hasher(36, FxBuildHasher);
-	let (mut max_row, max_col) = (0u16, input.lines().next().unwrap_or_default().len() as u16);
+	let (mut max_row, max_col) = (0u16, input.lines().next().unwrap_or_default().len().saturating_sub(1) as u16);
 	
 	for (row, line) in input.lines().enumerate().map(|(r, l)| (r as u16, l)) {
 		max_row = row;
@@ -54,7 +204,7 @@ pub fn part2(input: &str) -> usize {
 	let bounds = (max_row, max_col);
 	
 	let mut antinodes = HashSet::with_capacity_and_hasher(1500, FxBuildHasher);
-
+	
 	for (_, positions) in antennae {
 		if positions.len() <= 1 {
 			continue;
@@ -78,3 +228,53 @@ pub fn part2(input: &str) -> usize {
 	
 	antinodes.len()
 }
+
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
+fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
+	i.into_iter().enumerate()
+}
+
+fn map<I: IntoIterator, O, F: FnMut(I::Item) -> O>(i: I, f: F) -> iter::Map<I::IntoIter, F> {
+	i.into_iter().map(f)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "............\n........0...\n.....0......\n.......0....\n....0.......\n......A.....\n............\n............\n........A...\n.........A..\n............\n............\n";
+	
+	#[test]
+	fn example() {
+		assert_eq!((try_part1(EXAMPLE), try_part2(EXAMPLE)), (Ok(14), Ok(34)));
+	}
+	
+	#[test]
+	fn non_square() {
+		let wide = "c.........\n...c......\n..........\n";
+		let map = AntennaMap::try_parse(wide).unwrap();
+		assert_eq!((map.rows(), map.cols()), (3, 10));
+		assert_eq!(map.antinodes(Harmonics::PART1), [(2, 6)]);
+		assert_eq!(map.antinodes(Harmonics::PART2), [(0, 0), (1, 3), (2, 6)]);
+		assert_eq!(map.render(&map.antinodes(Harmonics::PART2)), "c.........\n...c......\n......#...\n");
+		
+		let tall = "c..\n...\n.c.\n...\n...\n...\n...\n...\n";
+		let map = AntennaMap::try_parse(tall).unwrap();
+		assert_eq!((map.rows(), map.cols()), (8, 3));
+		assert_eq!(map.antinodes(Harmonics::PART1), [(4, 2)]);
+		assert_eq!((try_part1(tall), try_part2(tall)), (Ok(1), Ok(3)));
+	}
+	
+	#[test]
+	fn wider_than_u16() {
+		let mut input = ".".repeat(70_000);
+		input.replace_range(0..1, "a");
+		input.replace_range(65_536..65_537, "a");
+		input.push('\n');
+		assert_eq!(AntennaMap::try_parse(&input).unwrap().antinodes(Harmonics::PART2), [(0, 0), (0, 65_536)]);
+		assert_eq!(try_part1(&input), Ok(0));
+	}
+}
@@ -1,4 +1,6 @@
-use std::{iter, num::NonZero};
+use std::{cmp, iter, mem, num::NonZero};
+
+use crate::error::{Checker, ParseError, ParseErrorKind};
 
 #[derive(Copy, Eq, Clone, PartialEq)]
 enum Kind {
@@ -139,11 +141,261 @@ fn compacted_checksum_bytes(files: &[u8]) -> u64 {
 	total
 }
 
+// A run of blocks belonging to one file. Files only ever end up split into more than one by block-level compaction.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct Extent {
+	pub id: u32,
+	pub start: u64,
+	pub size: u64
+}
+
+impl Extent {
+	fn end(self) -> u64 {
+		self.start + self.size
+	}
+	
+	fn checksum(self) -> u128 {
+		let (start, size) = (self.start as u128, self.size as u128);
+		self.id as u128 * (size * start + size * (size - 1) / 2)
+	}
+}
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum Compaction {
+	// Moves blocks one at a time from the end of the disk into the leftmost free block (part 1).
+	Blocks,
+	// Moves each file, highest id first, into the leftmost free span to its left that can fit all of it (part 2).
+	WholeFiles
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct DiskMap {
+	// Sorted by position, and never overlapping or empty.
+	extents: Vec<Extent>
+}
+
+impl DiskMap {
+	// Takes the size of each file and of the free space after it. Returns 'None' if there are too many files for their ids to fit in a 'u32'.
+	pub fn from_sizes(sizes: impl IntoIterator<Item = (u64, u64)>) -> Option<Self> {
+		let sizes = sizes.into_iter();
+		let mut extents = Vec::with_capacity(sizes.size_hint().0);
+		
+		let mut position = 0;
+		for (id, (size, free)) in enumerate(sizes) {
+			let id = u32::try_from(id).ok()?;
+			if size > 0 {
+				extents.push(Extent { id, start: position, size });
+			}
+			position += size + free;
+		}
+		
+		Some(Self { extents })
+	}
+	
+	pub fn parse(input: &str) -> Option<Self> {
+		let map = input.trim().as_bytes();
+		if !map.iter().all(u8::is_ascii_digit) {
+			return None;
+		}
+		
+		Self::from_sizes(map.chunks(2).map(|pair| match *pair {
+			[size, free] => ((size - b'0') as u64, (free - b'0') as u64),
+			[size, ..] => ((size - b'0') as u64, 0),
+			[] => (0, 0)
+		}))
+	}
+	
+	pub fn try_parse(input: &str) -> Result<Self, ParseError> {
+		check(input)?;
+		Ok(unsafe { Self::parse(input).unwrap_unchecked() })
+	}
+	
+	pub fn extents(&self) -> &[Extent] {
+		&self.extents
+	}
+	
+	// Up to the end of the last file.
+	pub fn len(&self) -> u64 {
+		self.extents.last().map_or(0, |extent| extent.end())
+	}
+	
+	pub fn is_empty(&self) -> bool {
+		self.extents.is_empty()
+	}
+	
+	pub fn compact(&mut self, mode: Compaction) {
+		match mode {
+			Compaction::Blocks => self.compact_blocks(),
+			Compaction::WholeFiles => self.compact_whole_files()
+		}
+	}
+	
+	fn push_merged(extents: &mut Vec<Extent>, extent: Extent) {
+		match extents.last_mut() {
+			Some(last) if last.id == extent.id && last.end() == extent.start => last.size += extent.size,
+			_ => extents.push(extent)
+		}
+	}
+	
+	fn compact_blocks(&mut self) {
+		let mut extents = mem::take(&mut self.extents);
+		let mut compacted = Vec::with_capacity(extents.len() + extents.len() / 2);
+		
+		let mut position = 0;
+		let (mut front, mut back) = (0, extents.len());
+		while front < back {
+			let extent = extents[front];
+			if extent.start > position {
+				// Fill the gap from the end of the last file, which may well be 'extent' itself.
+				let last = &mut extents[back - 1];
+				let moved = last.size.min(extent.start - position);
+				Self::push_merged(&mut compacted, Extent { id: last.id, start: position, size: moved });
+				position += moved;
+				last.size -= moved;
+				if last.size == 0 {
+					back -= 1;
+				}
+			} else {
+				Self::push_merged(&mut compacted, extent);
+				position = extent.end();
+				front += 1;
+			}
+		}
+		
+		self.extents = compacted;
+	}
+	
+	// Space freed up by moving a file isn't reused, which only makes a difference when files aren't laid out in order of id (like after
+	// block-level compaction). Files split into several extents have each extent moved separately, rightmost first.
+	fn compact_whole_files(&mut self) {
+		// Including any space before the first file, which there is when it's empty.
+		let extents = &self.extents;
+		let mut gaps = FreeSpans::new(map(0..extents.len(), |i| {
+			let free = if i == 0 { 0 } else { extents[i - 1].end() };
+			(free, extents[i].start - free)
+		}));
+		
+		let mut order: Vec<usize> = (0..self.extents.len()).collect();
+		order.sort_unstable_by_key(|&i| cmp::Reverse((self.extents[i].id, self.extents[i].start)));
+		
+		for i in order {
+			let extent = &mut self.extents[i];
+			// Spans are in order, so if the leftmost one that fits isn't before the file, none of them are.
+			if let Some(gap) = gaps.leftmost_fitting(extent.size).filter(|&gap| gaps.start(gap) < extent.start) {
+				extent.start = gaps.take(gap, extent.size);
+			}
+		}
+		
+		let mut extents = mem::take(&mut self.extents);
+		extents.sort_unstable_by_key(|extent| extent.start);
+		for extent in extents {
+			Self::push_merged(&mut self.extents, extent);
+		}
+	}
+	
+	pub fn checksum(&self) -> u128 {
+		self.extents.iter().map(|&extent| extent.checksum()).sum()
+	}
+	
+	// In the puzzle's notation, e.g. "0..111....22222". Returns 'None' if any id is more than one digit.
+	pub fn render(&self) -> Option<String> {
+		let mut rendered = String::with_capacity(self.len() as usize);
+		let mut position = 0;
+		for &Extent { id, start, size } in &self.extents {
+			let digit = char::from_digit(id, 10)?;
+			rendered.extend(iter::repeat_n('.', (start - position) as usize).chain(iter::repeat_n(digit, size as usize)));
+			position = start + size;
+		}
+		
+		Some(rendered)
+	}
+}
+
+// A max segment tree over the sizes of the free spans between extents (in order), for finding the leftmost one that fits a file.
+struct FreeSpans {
+	starts: Vec<u64>,
+	// Node 'i' has children '2i' and '2i + 1', and the leaves start at 'leaves'.
+	sizes: Vec<u64>,
+	leaves: usize
+}
+
+impl FreeSpans {
+	fn new(spans: impl ExactSizeIterator<Item = (u64, u64)>) -> Self {
+		let leaves = spans.len().next_power_of_two();
+		let mut starts = Vec::with_capacity(spans.len());
+		let mut sizes = vec![0; leaves * 2];
+		
+		for ((start, size), leaf) in spans.zip(&mut sizes[leaves..]) {
+			starts.push(start);
+			*leaf = size;
+		}
+		for node in (1..leaves).rev() {
+			sizes[node] = sizes[node * 2].max(sizes[node * 2 + 1]);
+		}
+		
+		Self { starts, sizes, leaves }
+	}
+	
+	fn start(&self, span: usize) -> u64 {
+		self.starts[span]
+	}
+	
+	fn leftmost_fitting(&self, size: u64) -> Option<usize> {
+		if self.sizes[1] < size {
+			return None;
+		}
+		
+		let mut node = 1;
+		while node < self.leaves {
+			node = if self.sizes[node * 2] >= size { node * 2 } else { node * 2 + 1 };
+		}
+		
+		Some(node - self.leaves)
+	}
+	
+	// Uses up the start of the span, returning where it was.
+	fn take(&mut self, span: usize, size: u64) -> u64 {
+		let start = self.starts[span];
+		self.starts[span] += size;
+		
+		let mut node = span + self.leaves;
+		self.sizes[node] -= size;
+		while node > 1 {
+			node /= 2;
+			self.sizes[node] = self.sizes[node * 2].max(self.sizes[node * 2 + 1]);
+		}
+		
+		start
+	}
+}
+
 #[aoc(day9, part1)]
-pub fn part1(input: &str) -> u64 {
+pub fn part1(input: &str) -> u128 {
+	let mut map = unsafe { DiskMap::parse(input).unwrap_unchecked() };
+	map.compact(Compaction::Blocks);
+	map.checksum()
+}
+
+#[allow(unused)]
+pub fn part1_v1(input: &str) -> u64 {
 	compacted_checksum_bytes(input.trim().as_bytes())
 }
 
+fn check(input: &str) -> Result<(), ParseError> {
+	let checker = Checker::new(9, input);
+	let map = input.trim();
+	checker.bytes_in(map, |b| b.is_ascii_digit())?;
+	
+	match map.get((u32::MAX as usize + 1) * 2..) { // File ids have to fit in a 'u32'.
+		Some(excess) if !excess.is_empty() => Err(checker.error_at(excess, ParseErrorKind::OutOfRange)),
+		_ => Ok(())
+	}
+}
+
+pub fn try_part1(input: &str) -> Result<u128, ParseError> {
+	check(input).map(|()| part1(input))
+}
+
 fn compact(files: &mut Vec<FileChunk>) {
 	// This implementation assumes that the input vector does not contain consecutive 'free' chunks, but can leave it in a state where it does.
 	// Luckily, the input doesn't seem to contain any 0-size files, and this only needs to run once, so it shouldn't actually matter in practice.
@@ -223,7 +475,14 @@ fn checksum(files: &[FileChunk]) -> u64 {
 }
 
 #[aoc(day9, part2)]
-pub fn part2(input: &str) -> u64 {
+pub fn part2(input: &str) -> u128 {
+	let mut map = unsafe { DiskMap::parse(input).unwrap_unchecked() };
+	map.compact(Compaction::WholeFiles);
+	map.checksum()
+}
+
+#[allow(unused)]
+pub fn part2_v1(input: &str) -> u64 {
 	let input = input.trim().as_bytes();
 	
 	let mut files = Vec::with_capacity(input.len() + input.len() / 16);
@@ -235,6 +494,10 @@ pub fn part2(input: &str) -> u64 {
 	checksum(&files)
 }
 
+pub fn try_part2(input: &str) -> Result<u128, ParseError> {
+	check(input).map(|()| part2(input))
+}
+
 fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 	i.into_iter().enumerate()
 }
@@ -242,3 +505,101 @@ fn enumerate<I: IntoIterator>(i: I) -> iter::Enumerate<I::IntoIter> {
 fn take<I: IntoIterator>(i: I, n: usize) -> iter::Take<I::IntoIter> {
 	i.into_iter().take(n)
 }
+
+fn map<I: IntoIterator, O, F: FnMut(I::Item) -> O>(i: I, f: F) -> iter::Map<I::IntoIter, F> {
+	i.into_iter().map(f)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const EXAMPLE: &str = "2333133121414131402";
+	
+	fn compacted(input: &str, mode: Compaction) -> DiskMap {
+		let mut disk = DiskMap::parse(input).unwrap();
+		disk.compact(mode);
+		disk
+	}
+	
+	fn to_blocks(disk: &DiskMap) -> Vec<Option<u32>> {
+		let mut blocks = vec![None; disk.len() as usize];
+		for window in disk.extents().windows(2) {
+			assert!(window[0].end() <= window[1].start, "{window:?}");
+		}
+		for &Extent { id, start, size } in disk.extents() {
+			assert!(size > 0);
+			blocks[start as usize..][..size as usize].fill(Some(id));
+		}
+		blocks
+	}
+	
+	// The puzzle's own description, one block at a time.
+	fn compact_by_blocks(sizes: &[(u64, u64)], mode: Compaction) -> Vec<Option<u32>> {
+		let mut blocks = Vec::new();
+		for (id, &(size, free)) in enumerate(sizes) {
+			blocks.extend(iter::repeat_n(Some(id as u32), size as usize));
+			blocks.extend(iter::repeat_n(None, free as usize));
+		}
+		
+		match mode {
+			Compaction::Blocks => {
+				while let (Some(free), Some(last)) = (blocks.iter().position(Option::is_none), blocks.iter().rposition(Option::is_some)) {
+					if free > last {
+						break;
+					}
+					blocks.swap(free, last);
+				}
+			},
+			Compaction::WholeFiles => {
+				for id in (0..sizes.len() as u32).rev() {
+					let Some(start) = blocks.iter().position(|&b| b == Some(id)) else { continue };
+					let size = sizes[id as usize].0 as usize;
+					if let Some(to) = (0..start).find(|&to| blocks[to..][..size].iter().all(Option::is_none)) {
+						blocks[to..][..size].fill(Some(id));
+						blocks[start..][..size].fill(None);
+					}
+				}
+			}
+		}
+		
+		while blocks.last() == Some(&None) {
+			blocks.pop();
+		}
+		blocks
+	}
+	
+	#[test]
+	fn example() {
+		assert_eq!(DiskMap::parse("12345").unwrap().render().unwrap(), "0..111....22222");
+		assert_eq!(compacted("12345", Compaction::Blocks).render().unwrap(), "022111222");
+		
+		let blocks = compacted(EXAMPLE, Compaction::Blocks);
+		assert_eq!(blocks.render().unwrap(), "0099811188827773336446555566");
+		assert_eq!(blocks.checksum(), 1928);
+		
+		let whole_files = compacted(EXAMPLE, Compaction::WholeFiles);
+		assert_eq!(whole_files.render().unwrap(), "00992111777.44.333....5555.6666.....8888");
+		assert_eq!(whole_files.checksum(), 2858);
+	}
+	
+	#[test]
+	fn matches_block_by_block_compaction() {
+		let mut seed = 0x2545f4914f6cdd1du64;
+		let mut random = |n: u64| {
+			seed ^= seed << 13;
+			seed ^= seed >> 7;
+			seed ^= seed << 17;
+			seed % n
+		};
+		
+		for _ in 0..500 {
+			let sizes: Vec<_> = (0..1 + random(30)).map(|_| (random(10), random(10))).collect();
+			for mode in [Compaction::Blocks, Compaction::WholeFiles] {
+				let mut disk = DiskMap::from_sizes(sizes.iter().copied()).unwrap();
+				disk.compact(mode);
+				assert_eq!(to_blocks(&disk), compact_by_blocks(&sizes, mode), "{sizes:?} {mode:?}");
+			}
+		}
+	}
+}
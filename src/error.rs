@@ -0,0 +1,162 @@
+use std::{error, fmt, iter, str::FromStr};
+
+use crate::grid::GridRefWithLn;
+
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub enum ParseErrorKind {
+	UnexpectedEnd,
+	UnexpectedByte(u8),
+	Expected(u8),
+	InvalidNumber,
+	OutOfRange,
+	RaggedRow,
+	Missing(u8),
+	Duplicate(u8),
+	Redefined,
+	Unsupported, // Well-formed, but not in a shape the solver can handle (e.g. a branching race track).
+	Unsolvable
+}
+
+// Lines and columns are both 1-based, with columns counted in bytes.
+#[derive(Copy, Eq, Clone, PartialEq, Debug)]
+pub struct ParseError {
+	pub day: u8,
+	pub line: usize,
+	pub column: usize,
+	pub kind: ParseErrorKind
+}
+
+impl ParseError {
+	pub fn new(day: u8, line: usize, column: usize, kind: ParseErrorKind) -> Self {
+		Self { day, line, column, kind }
+	}
+	
+	pub fn at_offset(day: u8, input: &str, offset: usize, kind: ParseErrorKind) -> Self {
+		let before = &input.as_bytes()[..offset.min(input.len())];
+		let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+		let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+		
+		Self::new(day, line, before.len() - line_start + 1, kind)
+	}
+}
+
+impl fmt::Display for ParseErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+			Self::UnexpectedByte(b) => write!(f, "unexpected '{}'", b.escape_ascii()),
+			Self::Expected(b) => write!(f, "expected '{}'", b.escape_ascii()),
+			Self::InvalidNumber => write!(f, "invalid number"),
+			Self::OutOfRange => write!(f, "value out of range"),
+			Self::RaggedRow => write!(f, "row length differs from the first row"),
+			Self::Missing(b) => write!(f, "no '{}' found", b.escape_ascii()),
+			Self::Duplicate(b) => write!(f, "more than one '{}'", b.escape_ascii()),
+			Self::Redefined => write!(f, "defined more than once"),
+			Self::Unsupported => write!(f, "input shape not supported by the solver"),
+			Self::Unsolvable => write!(f, "no solution exists")
+		}
+	}
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "day {}, line {}, column {}: {}", self.day, self.line, self.column, self.kind)
+	}
+}
+
+impl error::Error for ParseError {}
+
+// Shared helpers for the 'try_partN' functions. Every '&str' given to these must be a subslice of 'input'.
+#[derive(Copy, Clone)]
+pub(crate) struct Checker<'a> {
+	pub day: u8,
+	pub input: &'a str
+}
+
+impl<'a> Checker<'a> {
+	pub fn new(day: u8, input: &'a str) -> Self {
+		Self { day, input }
+	}
+	
+	pub fn offset_of(&self, at: &str) -> usize {
+		let offset = (at.as_ptr() as usize).wrapping_sub(self.input.as_ptr() as usize);
+		debug_assert!(offset <= self.input.len());
+		offset
+	}
+	
+	pub fn error_at(&self, at: &str, kind: ParseErrorKind) -> ParseError {
+		ParseError::at_offset(self.day, self.input, self.offset_of(at), kind)
+	}
+	
+	pub fn error_at_offset(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+		ParseError::at_offset(self.day, self.input, offset, kind)
+	}
+	
+	pub fn error_at_end(&self, of: &str, kind: ParseErrorKind) -> ParseError {
+		self.error_at_offset(self.offset_of(of) + of.len(), kind)
+	}
+	
+	pub fn number<T: FromStr>(&self, s: &str) -> Result<T, ParseError> {
+		if s.is_empty() {
+			Err(self.error_at(s, ParseErrorKind::UnexpectedEnd))
+		} else {
+			s.parse().map_err(|_| self.error_at(s, ParseErrorKind::InvalidNumber))
+		}
+	}
+	
+	pub fn prefix<'s>(&self, s: &'s str, prefix: &str) -> Result<&'s str, ParseError> {
+		s.strip_prefix(prefix).ok_or_else(|| {
+			let matching = s.bytes().zip(prefix.bytes()).take_while(|(a, b)| a == b).count();
+			match s.as_bytes().get(matching) {
+				Some(_) => self.error_at_offset(self.offset_of(s) + matching, ParseErrorKind::Expected(prefix.as_bytes()[matching])),
+				None => self.error_at_end(s, ParseErrorKind::UnexpectedEnd)
+			}
+		})
+	}
+	
+	pub fn split_once<'s>(&self, s: &'s str, delimiter: &str) -> Result<(&'s str, &'s str), ParseError> {
+		s.split_once(delimiter).ok_or_else(|| self.error_at_end(s, ParseErrorKind::Expected(delimiter.as_bytes()[0])))
+	}
+	
+	pub fn end(&self, s: &str) -> Result<(), ParseError> {
+		match s.as_bytes().first() {
+			Some(&b) => Err(self.error_at(s, ParseErrorKind::UnexpectedByte(b))),
+			None => Ok(())
+		}
+	}
+	
+	pub fn bytes_in(&self, s: &str, mut allowed: impl FnMut(u8) -> bool) -> Result<(), ParseError> {
+		match s.bytes().position(|b| !allowed(b)) {
+			Some(i) => Err(self.error_at_offset(self.offset_of(s) + i, ParseErrorKind::UnexpectedByte(s.as_bytes()[i]))),
+			None => Ok(())
+		}
+	}
+	
+	// Checks 's' is a '\n'-separated rectangle of allowed bytes (with or without a trailing '\n').
+	pub fn grid(&self, s: &'a str, allowed: impl FnMut(u8) -> bool + Copy) -> Result<GridRefWithLn<'a, u8>, ParseError> {
+		let mut rows = s.strip_suffix('\n').unwrap_or(s).split('\n');
+		let first = rows.next().unwrap_or_default();
+		if first.is_empty() {
+			return Err(self.error_at(first, s.bytes().next().map_or(ParseErrorKind::UnexpectedEnd, ParseErrorKind::UnexpectedByte)));
+		}
+		
+		for row in iter::once(first).chain(rows) {
+			self.bytes_in(row, allowed)?;
+			if row.len() != first.len() {
+				return Err(self.error_at_offset(self.offset_of(row) + row.len().min(first.len()), ParseErrorKind::RaggedRow));
+			}
+		}
+		
+		GridRefWithLn::from_text(s).ok_or_else(|| self.error_at_end(s, ParseErrorKind::RaggedRow))
+	}
+	
+	// Returns the offset (within 's') of the only occurrence of 'marker'.
+	pub fn unique(&self, s: &str, marker: u8) -> Result<usize, ParseError> {
+		let mut found = s.bytes().enumerate().filter_map(|(i, b)| (b == marker).then_some(i));
+		match (found.next(), found.next()) {
+			(Some(i), None) => Ok(i),
+			(Some(_), Some(i)) => Err(self.error_at_offset(self.offset_of(s) + i, ParseErrorKind::Duplicate(marker))),
+			(None, _) => Err(self.error_at_end(s, ParseErrorKind::Missing(marker)))
+		}
+	}
+}
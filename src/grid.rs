@@ -0,0 +1,581 @@
+use std::{iter, marker::PhantomData, num::NonZero, ptr::NonNull, slice};
+
+// Orthogonal neighbours in the order right, down, left, up.
+pub const OFFSETS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+fn neighbors_within(at: (usize, usize), row_count: usize, row_length: usize) -> impl Iterator<Item = (usize, usize)> + Clone {
+	let (row, col) = at;
+	filter_map(OFFSETS, move |(off_r, off_c)|
+		Option::zip(row.checked_add_signed(off_r), col.checked_add_signed(off_c)).filter(|&(r, c)| r < row_count && c < row_length)
+	)
+}
+
+fn length_with_ln(len: usize, row_length: NonZero<usize>) -> Option<(NonZero<usize>, NonZero<usize>)> {
+	let row_length_with_ln = row_length.checked_add(1)?;
+	let total_length_with_ln = NonZero::new(
+		if len % row_length_with_ln == 0 {
+			len
+		} else {
+			len + 1
+		}
+	)?;
+	
+	(total_length_with_ln.get() % row_length_with_ln == 0).then_some((total_length_with_ln, row_length_with_ln))
+}
+
+ // - Borrowed - //
+
+// A grid borrowed from text where every row is followed by a single separator (normally '\n'), with the final one being optional.
+pub struct GridRefWithLn<'a, T> {
+	grid_ptr: NonNull<T>,
+	total_length_with_ln: NonZero<usize>,
+	row_length_with_ln: NonZero<usize>,
+	marker: PhantomData<&'a [T]>
+}
+
+impl<T> Copy for GridRefWithLn<'_, T> {}
+
+impl<T> Clone for GridRefWithLn<'_, T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<'a, T> GridRefWithLn<'a, T> {
+	pub fn from_slice(grid: &'a [T], row_length: NonZero<usize>) -> Option<Self> {
+		let (total_length_with_ln, row_length_with_ln) = length_with_ln(grid.len(), row_length)?;
+		
+		Some(Self {
+			grid_ptr: unsafe { NonNull::new_unchecked(grid.as_ptr() as *mut _) },
+			total_length_with_ln,
+			row_length_with_ln,
+			marker: PhantomData
+		})
+	}
+	
+	/// # Safety
+	/// `grid_ptr` must be valid for reads of `total_length_with_ln - 1` elements for `'a`, and `total_length_with_ln` must be a
+	/// multiple of `row_length_with_ln` (which must be at least 2).
+	pub unsafe fn from_raw_parts(grid_ptr: NonNull<T>, total_length_with_ln: NonZero<usize>, row_length_with_ln: NonZero<usize>) -> Self {
+		Self { grid_ptr, total_length_with_ln, row_length_with_ln, marker: PhantomData }
+	}
+	
+	
+	pub fn as_slice(&self) -> &'a [T] { // Does not include the final possibly-uninitialized '\n'
+		unsafe { slice::from_raw_parts(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
+	}
+	
+	pub fn as_ptr(&self) -> NonNull<T> {
+		self.grid_ptr
+	}
+	
+	
+	pub fn get(&self, row: usize, col: usize) -> Option<&'a T> {
+		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
+	}
+	
+	/// # Safety
+	/// `row` and `col` must be within the grid.
+	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &'a T {
+		unsafe { self.as_slice().get_unchecked(self.to_index_unchecked(row, col)) }
+	}
+	
+	
+	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
+		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
+	}
+	
+	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
+		row * self.row_length_with_ln().get() + col
+	}
+	
+	pub fn to_row_col(&self, index: usize) -> Option<(usize, usize)> {
+		(index < self.total_length_with_ln().get()).then(|| self.to_row_col_unchecked(index)).filter(|&(_, c)| c < self.row_length().get())
+	}
+	
+	pub fn to_row_col_unchecked(&self, index: usize) -> (usize, usize) {
+		(index / self.row_length_with_ln(), index % self.row_length_with_ln())
+	}
+	
+	pub fn neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + Clone {
+		neighbors_within((row, col), self.row_count().get(), self.row_length().get())
+	}
+	
+	
+	pub fn get_row(&self, row: usize) -> Option<&'a [T]> {
+		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
+		let row_end = row_start + self.row_length().get();
+		self.as_slice().get(row_start..row_end)
+	}
+	
+	pub fn rows(&self) -> Rows<'a, T> {
+		Rows::over(*self)
+	}
+	
+	
+	pub fn row_count(&self) -> NonZero<usize> {
+		unsafe { NonZero::new_unchecked(self.total_length_with_ln().get() / self.row_length_with_ln()) }
+	}
+	
+	pub fn row_length(&self) -> NonZero<usize> {
+		unsafe { NonZero::new_unchecked(self.row_length_with_ln().get() - 1) }
+	}
+	
+	pub fn row_length_with_ln(&self) -> NonZero<usize> {
+		self.row_length_with_ln
+	}
+	
+	pub fn total_length_with_ln(&self) -> NonZero<usize> {
+		self.total_length_with_ln
+	}
+}
+
+impl<'a> GridRefWithLn<'a, u8> {
+	// Uses the position of the first '\n' as the row length.
+	pub fn from_text(text: &'a str) -> Option<Self> {
+		let row_length = text.find('\n').unwrap_or(text.len());
+		NonZero::new(row_length).and_then(|row_length| Self::from_slice(text.as_bytes(), row_length))
+	}
+}
+
+pub struct Rows<'a, T> {
+	src: Option<GridRefWithLn<'a, T>>,
+}
+
+impl<T> Clone for Rows<'_, T> {
+	fn clone(&self) -> Self {
+		Self { src: self.as_grid_ref() }
+	}
+}
+
+impl<'a, T> Rows<'a, T> {
+	pub fn over(src: GridRefWithLn<'a, T>) -> Self {
+		Self { src: src.into() }
+	}
+	
+	pub fn as_grid_ref(&self) -> Option<GridRefWithLn<'a, T>> {
+		self.src
+	}
+	
+	pub fn empty() -> Self {
+		Self { src: None }
+	}
+}
+
+impl<'a, T> Iterator for Rows<'a, T> {
+	type Item = &'a [T];
+	
+	fn next(&mut self) -> Option<Self::Item> {
+		self.src.map(|src| {
+			let slice = unsafe { src.as_slice().get_unchecked(..src.row_length().get()) };
+			
+			let (ptr, total_ln, row_ln) = (src.as_ptr(), src.total_length_with_ln(), src.row_length_with_ln());
+			
+			self.src = NonZero::new(unsafe { total_ln.get().unchecked_sub(row_ln.get()) }).map(|new_total_ln| unsafe {
+				let new_ptr = ptr.add(row_ln.get());
+				GridRefWithLn::from_raw_parts(new_ptr, new_total_ln, row_ln)
+			});
+			
+			slice
+		})
+	}
+	
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let len = self.len();
+		(len, len.into())
+	}
+}
+
+impl<T> DoubleEndedIterator for Rows<'_, T> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		self.src.map(|src| {
+			let (ptr, total_ln, row_ln) = (src.as_ptr(), src.total_length_with_ln(), src.row_length_with_ln());
+			
+			let start = unsafe { total_ln.get().unchecked_sub(row_ln.get()) };
+			let end = start + src.row_length().get();
+			
+			let slice = unsafe { src.as_slice().get_unchecked(start..end) };
+			
+			self.src = NonZero::new(start).map(|new_total_ln| unsafe {
+				GridRefWithLn::from_raw_parts(ptr, new_total_ln, row_ln)
+			});
+			
+			slice
+		})
+	}
+}
+
+impl<T> ExactSizeIterator for Rows<'_, T> {
+	fn len(&self) -> usize {
+		self.src.map(|src| src.total_length_with_ln().get() / src.row_length_with_ln()).unwrap_or(0)
+	}
+}
+
+ // - Mutably borrowed - //
+
+pub struct GridRefMutWithLn<'a, T> {
+	grid_ptr: NonNull<T>,
+	total_length_with_ln: NonZero<usize>,
+	row_length_with_ln: NonZero<usize>,
+	marker: PhantomData<&'a mut [T]>
+}
+
+impl<'a, T> GridRefMutWithLn<'a, T> {
+	pub fn from_slice(grid: &'a mut [T], row_length: NonZero<usize>) -> Option<Self> {
+		let (total_length_with_ln, row_length_with_ln) = length_with_ln(grid.len(), row_length)?;
+		
+		Some(Self {
+			grid_ptr: unsafe { NonNull::new_unchecked(grid.as_mut_ptr()) },
+			total_length_with_ln,
+			row_length_with_ln,
+			marker: PhantomData
+		})
+	}
+	
+	/// # Safety
+	/// As [`GridRefWithLn::from_raw_parts`], but `grid_ptr` must also be valid for writes and not aliased for `'a`.
+	pub unsafe fn from_raw_parts(grid_ptr: NonNull<T>, total_length_with_ln: NonZero<usize>, row_length_with_ln: NonZero<usize>) -> Self {
+		Self { grid_ptr, total_length_with_ln, row_length_with_ln, marker: PhantomData }
+	}
+	
+	pub fn as_grid_ref(&self) -> GridRefWithLn<'_, T> {
+		unsafe { GridRefWithLn::from_raw_parts(self.grid_ptr, self.total_length_with_ln, self.row_length_with_ln) }
+	}
+	
+	
+	pub fn as_slice(&self) -> &[T] { // Does not include the final possibly-uninitialized '\n'
+		unsafe { slice::from_raw_parts(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
+	}
+	
+	pub fn as_mut_slice(&mut self) -> &mut [T] { // Does not include the final possibly-uninitialized '\n'
+		unsafe { slice::from_raw_parts_mut(self.grid_ptr.as_ptr(), self.total_length_with_ln().get() - 1) }
+	}
+	
+	pub fn as_ptr(&self) -> NonNull<T> {
+		self.grid_ptr
+	}
+	
+	pub fn as_mut_ptr(&mut self) -> NonNull<T> {
+		self.grid_ptr
+	}
+	
+	
+	pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
+	}
+	
+	pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+		self.to_index(row, col).map(|i| unsafe { self.as_mut_slice().get_unchecked_mut(i) })
+	}
+	
+	/// # Safety
+	/// `row` and `col` must be within the grid.
+	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &T {
+		unsafe { self.as_slice().get_unchecked(self.to_index_unchecked(row, col)) }
+	}
+	
+	/// # Safety
+	/// `row` and `col` must be within the grid.
+	pub unsafe fn get_unchecked_mut(&mut self, row: usize, col: usize) -> &mut T {
+		let index = self.to_index_unchecked(row, col);
+		unsafe { self.as_mut_slice().get_unchecked_mut(index) }
+	}
+	
+	
+	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
+		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
+	}
+	
+	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
+		row * self.row_length_with_ln().get() + col
+	}
+	
+	pub fn to_row_col(&self, index: usize) -> Option<(usize, usize)> {
+		(index < self.total_length_with_ln().get()).then(|| self.to_row_col_unchecked(index)).filter(|&(_, c)| c < self.row_length().get())
+	}
+	
+	pub fn to_row_col_unchecked(&self, index: usize) -> (usize, usize) {
+		(index / self.row_length_with_ln(), index % self.row_length_with_ln())
+	}
+	
+	pub fn neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + Clone {
+		neighbors_within((row, col), self.row_count().get(), self.row_length().get())
+	}
+	
+	
+	pub fn get_row(&self, row: usize) -> Option<&[T]> {
+		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
+		let row_end = row_start + self.row_length().get();
+		self.as_slice().get(row_start..row_end)
+	}
+	
+	pub fn get_row_mut(&mut self, row: usize) -> Option<&mut [T]> {
+		let row_start = row.checked_mul(self.row_length_with_ln().get())?;
+		let row_end = row_start + self.row_length().get();
+		self.as_mut_slice().get_mut(row_start..row_end)
+	}
+	
+	pub fn rows(&self) -> Rows<'_, T> {
+		self.as_grid_ref().rows()
+	}
+	
+	
+	pub fn row_count(&self) -> NonZero<usize> {
+		unsafe { NonZero::new_unchecked(self.total_length_with_ln().get() / self.row_length_with_ln()) }
+	}
+	
+	pub fn row_length(&self) -> NonZero<usize> {
+		unsafe { NonZero::new_unchecked(self.row_length_with_ln().get() - 1) }
+	}
+	
+	pub fn row_length_with_ln(&self) -> NonZero<usize> {
+		self.row_length_with_ln
+	}
+	
+	pub fn total_length_with_ln(&self) -> NonZero<usize> {
+		self.total_length_with_ln
+	}
+}
+
+ // - Owned - //
+
+#[derive(Clone)]
+pub struct Grid<T> {
+	grid: Vec<T>,
+	row_length: NonZero<usize>
+}
+
+impl<T> Grid<T> {
+	pub fn from_vec(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
+		assert_eq!(grid.len() % row_length, 0);
+		assert!( !grid.is_empty() );
+		
+		unsafe { Self::from_vec_unchecked(grid, row_length) }
+	}
+	
+	pub fn from_first_row(grid: Vec<T>) -> Result<Self, Vec<T>> {
+		if let Some(row_length) = NonZero::new(grid.len()) {
+			Ok(unsafe { Self::from_vec_unchecked(grid, row_length) })
+		} else {
+			Err(grid)
+		}
+	}
+	
+	/// # Safety
+	/// `grid` must be non-empty and its length a multiple of `row_length`.
+	pub unsafe fn from_vec_unchecked(grid: Vec<T>, row_length: NonZero<usize>) -> Self {
+		Self { grid, row_length }
+	}
+	
+	/// # Safety
+	/// The length of the vector must remain a non-zero multiple of the row length.
+	pub unsafe fn as_vec_mut(&mut self) -> &mut Vec<T> {
+		&mut self.grid
+	}
+	
+	pub fn into_vec(self) -> Vec<T> {
+		self.grid
+	}
+	
+	pub fn reserve_rows(&mut self, rows: usize) {
+		let capacity = self.row_length().get() * rows;
+		unsafe {
+			self.as_vec_mut().reserve(capacity);
+		}
+	}
+	
+	pub fn add_filled_row(&mut self, value: T) -> &mut [T] where T: Clone {
+		let prev_len = self.total_length().get();
+		let row_len = self.row_length().get();
+		unsafe {
+			self.as_vec_mut().resize(prev_len + row_len, value);
+			self.as_mut_slice().get_unchecked_mut(prev_len..)
+		}
+	}
+	
+	
+	pub fn as_slice(&self) -> &[T] {
+		&self.grid
+	}
+	
+	pub fn as_mut_slice(&mut self) -> &mut [T] {
+		&mut self.grid
+	}
+	
+	
+	pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+		self.to_index(row, col).map(|i| unsafe { self.as_slice().get_unchecked(i) })
+	}
+	
+	pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+		self.to_index(row, col).map(|i| unsafe { self.as_mut_slice().get_unchecked_mut(i) })
+	}
+	
+	/// # Safety
+	/// `row` and `col` must be within the grid.
+	pub unsafe fn get_unchecked(&self, row: usize, col: usize) -> &T {
+		unsafe { self.as_slice().get_unchecked(self.to_index_unchecked(row, col)) }
+	}
+	
+	/// # Safety
+	/// `row` and `col` must be within the grid.
+	pub unsafe fn get_unchecked_mut(&mut self, row: usize, col: usize) -> &mut T {
+		let index = self.to_index_unchecked(row, col);
+		unsafe { self.as_mut_slice().get_unchecked_mut(index) }
+	}
+	
+	
+	pub fn to_index(&self, row: usize, col: usize) -> Option<usize> {
+		(row < self.row_count().get() && col < self.row_length().get()).then(|| self.to_index_unchecked(row, col))
+	}
+	
+	pub fn to_index_unchecked(&self, row: usize, col: usize) -> usize {
+		row * self.row_length().get() + col
+	}
+	
+	pub fn to_row_col(&self, index: usize) -> Option<(usize, usize)> {
+		(index < self.total_length().get()).then(|| self.to_row_col_unchecked(index))
+	}
+	
+	pub fn to_row_col_unchecked(&self, index: usize) -> (usize, usize) {
+		(index / self.row_length(), index % self.row_length())
+	}
+	
+	pub fn neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + Clone {
+		neighbors_within((row, col), self.row_count().get(), self.row_length().get())
+	}
+	
+	
+	pub fn get_row(&self, row: usize) -> Option<&[T]> {
+		let row_start = row.checked_mul(self.row_length().get())?;
+		let row_end = row_start + self.row_length().get();
+		self.as_slice().get(row_start..row_end)
+	}
+	
+	pub fn get_row_mut(&mut self, row: usize) -> Option<&mut [T]> {
+		let row_start = row.checked_mul(self.row_length().get())?;
+		let row_end = row_start + self.row_length().get();
+		self.as_mut_slice().get_mut(row_start..row_end)
+	}
+	
+	pub fn rows(&self) -> slice::ChunksExact<'_, T> {
+		self.as_slice().chunks_exact(self.row_length().get())
+	}
+	
+	pub fn rows_mut(&mut self) -> slice::ChunksExactMut<'_, T> {
+		let row_length = self.row_length().get();
+		self.as_mut_slice().chunks_exact_mut(row_length)
+	}
+	
+	
+	pub fn row_count(&self) -> NonZero<usize> {
+		unsafe { NonZero::new_unchecked(self.total_length().get() / self.row_length()) }
+	}
+	
+	pub fn row_length(&self) -> NonZero<usize> {
+		self.row_length
+	}
+	
+	pub fn total_length(&self) -> NonZero<usize> {
+		unsafe { NonZero::new_unchecked(self.as_slice().len()) }
+	}
+}
+
+impl<T: Clone> Grid<T> {
+	pub fn filled(value: T, row_length: NonZero<usize>, row_count: NonZero<usize>) -> Self {
+		unsafe { Self::from_vec_unchecked(vec![value; row_length.get() * row_count.get()], row_length) }
+	}
+}
+
+fn filter_map<I: IntoIterator, O, F: FnMut(I::Item) -> Option<O>>(i: I, f: F) -> iter::FilterMap<I::IntoIter, F> {
+	i.into_iter().filter_map(f)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	const TEXT: &str = "abc\ndef\n";
+	
+	#[test]
+	fn from_text_with_and_without_final_ln() {
+		for text in [TEXT, TEXT.trim_end()] {
+			let grid = GridRefWithLn::from_text(text).unwrap();
+			assert_eq!((grid.row_count().get(), grid.row_length().get()), (2, 3));
+			assert_eq!(grid.as_slice(), b"abc\ndef");
+		}
+		
+		assert!(GridRefWithLn::from_text("").is_none());
+		assert!(GridRefWithLn::from_text("abc\nd\n").is_none()); // Only the total length is checked, not where each '\n' is.
+	}
+	
+	#[test]
+	fn get_at_edges() {
+		let grid = GridRefWithLn::from_text(TEXT).unwrap();
+		assert_eq!(grid.get(0, 0), Some(&b'a'));
+		assert_eq!(grid.get(1, 2), Some(&b'f'));
+		assert_eq!(grid.get(0, 3), None); // The '\n'.
+		assert_eq!(grid.get(2, 0), None);
+		assert_eq!(grid.get(usize::MAX, usize::MAX), None);
+		assert_eq!(grid.get_row(1), Some(&b"def"[..]));
+		assert_eq!(grid.get_row(2), None);
+		assert_eq!(grid.get_row(usize::MAX), None);
+	}
+	
+	#[test]
+	fn rows_from_both_ends() {
+		let grid = GridRefWithLn::from_text(TEXT).unwrap();
+		assert_eq!(grid.rows().collect::<Vec<_>>(), [b"abc", b"def"]);
+		assert_eq!(grid.rows().rev().collect::<Vec<_>>(), [b"def", b"abc"]);
+		assert_eq!(grid.rows().len(), 2);
+		
+		let mut rows = grid.rows();
+		assert_eq!(rows.next(), Some(&b"abc"[..]));
+		assert_eq!(rows.next_back(), Some(&b"def"[..]));
+		assert_eq!((rows.next(), rows.next_back(), rows.len()), (None, None, 0));
+	}
+	
+	#[test]
+	fn index_round_trip() {
+		let grid = GridRefWithLn::from_text(TEXT).unwrap();
+		assert_eq!(grid.to_index(1, 2), Some(6));
+		assert_eq!(grid.to_index(1, 3), None);
+		assert_eq!(grid.to_row_col(6), Some((1, 2)));
+		assert_eq!(grid.to_row_col(3), None); // The '\n'.
+		assert_eq!(grid.to_row_col(8), None);
+		
+		let owned = Grid::from_vec(b"abcdef".to_vec(), NonZero::new(3).unwrap());
+		assert_eq!(owned.to_index(1, 2), Some(5));
+		assert_eq!(owned.to_row_col(5), Some((1, 2)));
+		assert_eq!(owned.to_row_col(6), None);
+	}
+	
+	#[test]
+	fn neighbors_stay_within() {
+		let grid = GridRefWithLn::from_text(TEXT).unwrap();
+		assert_eq!(grid.neighbors(0, 0).collect::<Vec<_>>(), [(0, 1), (1, 0)]);
+		assert_eq!(grid.neighbors(1, 2).collect::<Vec<_>>(), [(1, 1), (0, 2)]);
+		assert_eq!(grid.neighbors(0, 1).collect::<Vec<_>>(), [(0, 2), (1, 1), (0, 0)]);
+		
+		let single = Grid::from_vec(vec![0u8], NonZero::new(1).unwrap());
+		assert_eq!(single.neighbors(0, 0).count(), 0);
+	}
+	
+	#[test]
+	fn owned_rows() {
+		let mut grid = Grid::from_first_row(vec![1, 2]).unwrap();
+		grid.add_filled_row(0).copy_from_slice(&[3, 4]);
+		assert_eq!(grid.rows().collect::<Vec<_>>(), [[1, 2], [3, 4]]);
+		assert_eq!((grid.get(1, 1), grid.get(2, 0), grid.get(0, 2)), (Some(&4), None, None));
+		assert!(Grid::<u8>::from_first_row(Vec::new()).is_err());
+	}
+	
+	#[test]
+	fn mut_grid_writes_through() {
+		let mut text = TEXT.as_bytes().to_vec();
+		let mut grid = GridRefMutWithLn::from_slice(&mut text, NonZero::new(3).unwrap()).unwrap();
+		*grid.get_mut(1, 0).unwrap() = b'x';
+		assert!(grid.get_mut(0, 3).is_none());
+		assert_eq!(text, b"abc\nxef\n");
+	}
+}
@@ -5,6 +5,9 @@ extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod error;
+pub mod grid;
+
 pub mod day1;
 pub mod day2;
 pub mod day3;